@@ -0,0 +1,147 @@
+use crate::errors::{AccError, AccResult};
+use crate::files;
+use crate::models::SetupRef;
+use log::info;
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Largest decompressed size accepted for a single pack entry. Setup files
+/// are a few kilobytes.
+const MAX_ENTRY_SIZE: u64 = 1024 * 1024;
+
+/// Largest decompressed size accepted for a whole pack
+const MAX_PACK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A setup file read out of a zip pack
+pub struct PackEntry {
+    /// Path of the entry inside the archive
+    pub name: String,
+    /// Car, track and file folder names, if laid out as `<car>/<track>/<file>`
+    pub location: Option<(String, String, String)>,
+    pub content: Result<String, String>,
+}
+
+/// Write the selected setups into a zip pack laid out as `<car>/<track>/<file>.json`.
+/// File contents are copied verbatim, including ACCSMData.
+pub fn export_setups(setups_path: &Path, selection: &[SetupRef], dest: &Path) -> AccResult<usize> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for setup in selection {
+        let file_path = setups_path
+            .join(&setup.car)
+            .join(&setup.track)
+            .join(&setup.filename);
+
+        let content = fs::read(&file_path).map_err(|e| AccError::IoError {
+            message: format!(
+                "Failed to read setup file {}: {}",
+                file_path.to_string_lossy(),
+                e
+            ),
+        })?;
+
+        let entry_name = format!("{}/{}/{}", setup.car, setup.track, setup.filename);
+        writer
+            .start_file(entry_name, options)
+            .map_err(|e| archive_error(dest, e))?;
+        writer
+            .write_all(&content)
+            .map_err(|e| archive_error(dest, e))?;
+    }
+
+    let buffer = writer.finish().map_err(|e| archive_error(dest, e))?;
+    files::write_atomic(dest, buffer.get_ref()).map_err(|e| AccError::FileWriteFailed {
+        path: dest.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })?;
+
+    info!("Exported {} setups to {:?}", selection.len(), dest);
+    Ok(selection.len())
+}
+
+/// Read every file entry out of a zip pack. Entries larger than
+/// `MAX_ENTRY_SIZE` are reported instead of read, and packs decompressing to
+/// more than `MAX_PACK_SIZE` are rejected.
+pub fn read_pack(zip_path: &Path) -> AccResult<Vec<PackEntry>> {
+    let file = File::open(zip_path).map_err(|e| AccError::IoError {
+        message: format!("Failed to open setup pack: {}", e),
+    })?;
+    let mut archive = ZipArchive::new(file).map_err(|e| archive_error(zip_path, e))?;
+
+    let mut entries = Vec::new();
+    let mut total_size = 0;
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| archive_error(zip_path, e))?;
+
+        if entry.is_dir() {
+            continue;
+        }
+
+        let name = entry.name().to_string();
+
+        // Reject entries that would escape the archive root
+        let Some(enclosed) = entry.enclosed_name() else {
+            entries.push(PackEntry {
+                name,
+                location: None,
+                content: Err("Unsafe path inside archive".to_string()),
+            });
+            continue;
+        };
+
+        let components: Vec<String> = enclosed
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        // Packs are often wrapped in a top-level folder, so use the last three parts
+        let location = match components.as_slice() {
+            [.., car, track, filename] => Some((car.clone(), track.clone(), filename.clone())),
+            _ => None,
+        };
+
+        // The declared size can lie, so the read itself is capped as well
+        let mut content = String::new();
+        let content = match entry
+            .by_ref()
+            .take(MAX_ENTRY_SIZE + 1)
+            .read_to_string(&mut content)
+        {
+            Ok(size) if size as u64 > MAX_ENTRY_SIZE => {
+                Err(format!("Entry is larger than {} bytes", MAX_ENTRY_SIZE))
+            }
+            Ok(size) => {
+                total_size += size as u64;
+                Ok(content)
+            }
+            Err(e) => Err(format!("Failed to read archive entry: {}", e)),
+        };
+        if total_size > MAX_PACK_SIZE {
+            return Err(archive_error(
+                zip_path,
+                format!("Pack is larger than {} bytes", MAX_PACK_SIZE),
+            ));
+        }
+
+        entries.push(PackEntry {
+            name,
+            location,
+            content,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn archive_error(path: &Path, error: impl std::fmt::Display) -> AccError {
+    AccError::ArchiveFailed {
+        path: path.to_string_lossy().to_string(),
+        error: error.to_string(),
+    }
+}
@@ -1,21 +1,33 @@
+use crate::config::{AppConfig, LibraryConfig};
 use crate::data;
+use crate::diff;
 use crate::errors::AccError;
-use crate::models::{Car, FolderStructure, SetupFile, Track};
+use crate::models::{
+    AliasMatch, Car, FolderStructure, FuelStrategy, FuelStrategyRequest, ImportReport,
+    ParameterChange, ParameterConversion, ParameterLimit, PhysicalSetup, ScanDiagnostic,
+    SearchResult, SetupFile, SetupInfo, SetupMetadata, SetupQuery, SetupRef, SetupRevision,
+    SetupTransfer, SetupValidation, TagUpdateReport, TagUsage, Track, TrashEntry,
+};
 use crate::state::AppStateManager;
+use crate::units;
+use crate::validation;
+use crate::watcher::{FileWatcher, SharedWatcher};
 use log::{error, info};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::State;
+use tauri::{AppHandle, State};
 
 /// Get the complete folder structure
 #[tauri::command]
 pub async fn get_folder_structure(
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
 ) -> Result<FolderStructure, AccError> {
+    let library = state.library(library.as_deref()).await?;
     info!("Getting folder structure");
-    match state.get_folder_structure().await {
+    match library.get_folder_structure().await {
         Ok(structure) => {
             info!(
                 "Retrieved folder structure with {} cars",
@@ -30,16 +42,37 @@ pub async fn get_folder_structure(
     }
 }
 
+/// Get the files and folders the last scan could not list
+#[tauri::command]
+pub async fn get_scan_diagnostics(
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<ScanDiagnostic>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    match library.get_folder_structure().await {
+        Ok(structure) => {
+            info!("Found {} scan diagnostics", structure.diagnostics.len());
+            Ok(structure.diagnostics)
+        }
+        Err(e) => {
+            error!("Failed to get scan diagnostics: {}", e);
+            Err(e)
+        }
+    }
+}
+
 /// Get a specific setup file
 #[tauri::command]
 pub async fn get_setup(
     car: String,
     track: String,
     filename: String,
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
 ) -> Result<SetupFile, AccError> {
+    let library = state.library(library.as_deref()).await?;
     info!("Getting setup: {}/{}/{}", car, track, filename);
-    match state.read_setup(&car, &track, &filename).await {
+    match library.read_setup(&car, &track, &filename).await {
         Ok(setup) => {
             info!(
                 "Successfully retrieved setup: {}/{}/{}",
@@ -61,10 +94,12 @@ pub async fn save_setup(
     track: String,
     filename: String,
     content: JsonValue,
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
 ) -> Result<(), AccError> {
+    let library = state.library(library.as_deref()).await?;
     info!("Saving setup: {}/{}/{}", car, track, filename);
-    match state.save_setup(&car, &track, &filename, content).await {
+    match library.save_setup(&car, &track, &filename, content).await {
         Ok(()) => {
             info!("Successfully saved setup: {}/{}/{}", car, track, filename);
             Ok(())
@@ -83,12 +118,14 @@ pub async fn edit_setup(
     track: String,
     filename: String,
     content: JsonValue,
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
 ) -> Result<(), AccError> {
+    let library = state.library(library.as_deref()).await?;
     info!("Editing setup: {}/{}/{}", car, track, filename);
 
     // For editing, we use the same save_setup function as it handles metadata updates
-    match state.save_setup(&car, &track, &filename, content).await {
+    match library.save_setup(&car, &track, &filename, content).await {
         Ok(()) => {
             info!("Successfully edited setup: {}/{}/{}", car, track, filename);
             Ok(())
@@ -100,16 +137,51 @@ pub async fn edit_setup(
     }
 }
 
+/// Update the metadata of a setup without touching its setup values
+#[tauri::command]
+pub async fn update_setup_metadata(
+    car: String,
+    track: String,
+    filename: String,
+    metadata: SetupMetadata,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<SetupInfo, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Updating metadata: {}/{}/{}", car, track, filename);
+    match library
+        .update_setup_metadata(&car, &track, &filename, metadata)
+        .await
+    {
+        Ok(setup) => {
+            info!(
+                "Successfully updated metadata: {}/{}/{}",
+                car, track, filename
+            );
+            Ok(setup)
+        }
+        Err(e) => {
+            error!(
+                "Failed to update metadata {}/{}/{}: {}",
+                car, track, filename, e
+            );
+            Err(e)
+        }
+    }
+}
+
 /// Delete a setup file
 #[tauri::command]
 pub async fn delete_setup(
     car: String,
     track: String,
     filename: String,
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
 ) -> Result<(), AccError> {
+    let library = state.library(library.as_deref()).await?;
     info!("Deleting setup: {}/{}/{}", car, track, filename);
-    match state.delete_setup(&car, &track, &filename).await {
+    match library.delete_setup(&car, &track, &filename).await {
         Ok(()) => {
             info!("Successfully deleted setup: {}/{}/{}", car, track, filename);
             Ok(())
@@ -128,13 +200,21 @@ pub async fn delete_setup(
 #[tauri::command]
 pub async fn set_setups_path(
     path: String,
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
+    watcher: State<'_, SharedWatcher>,
 ) -> Result<(), AccError> {
+    let library = state.library(library.as_deref()).await?;
     info!("Setting setups path: {}", path);
     let path_buf = PathBuf::from(path);
-    match state.set_setups_path(path_buf).await {
+    match library.set_setups_path(path_buf.clone()).await {
         Ok(()) => {
             info!("Successfully updated setups path");
+            if let Some(file_watcher) = watcher.lock().await.get_mut(library.id()) {
+                if let Err(e) = file_watcher.update_watch_path(&path_buf) {
+                    error!("Failed to update watched path: {}", e);
+                }
+            }
             Ok(())
         }
         Err(e) => {
@@ -146,8 +226,12 @@ pub async fn set_setups_path(
 
 /// Get the current setups folder path
 #[tauri::command]
-pub async fn get_setups_path(state: State<'_, Arc<AppStateManager>>) -> Result<String, AccError> {
-    let path = state.get_setups_path().await;
+pub async fn get_setups_path(
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<String, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    let path = library.get_setups_path().await;
     let path_str = path.to_string_lossy().to_string();
     info!("Current setups path: {}", path_str);
     Ok(path_str)
@@ -174,11 +258,13 @@ pub async fn get_tracks() -> Result<HashMap<String, Track>, AccError> {
 /// Refresh the folder structure (force rescan)
 #[tauri::command]
 pub async fn refresh_folder_structure(
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
 ) -> Result<FolderStructure, AccError> {
+    let library = state.library(library.as_deref()).await?;
     info!("Force refreshing folder structure");
-    match state.refresh_folder_structure().await {
-        Ok(()) => match state.get_folder_structure().await {
+    match library.refresh_folder_structure().await {
+        Ok(()) => match library.get_folder_structure().await {
             Ok(structure) => {
                 info!(
                     "Successfully refreshed folder structure with {} cars",
@@ -198,64 +284,892 @@ pub async fn refresh_folder_structure(
     }
 }
 
+/// Find where a setup currently lives by its stable id
+#[tauri::command]
+pub async fn find_setup_by_id(
+    id: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Option<SetupRef>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    match library.find_setup_by_id(&id).await {
+        Ok(setup) => Ok(setup),
+        Err(e) => {
+            error!("Failed to look up setup {}: {}", id, e);
+            Err(e)
+        }
+    }
+}
+
 /// Check if a setup file exists
 #[tauri::command]
 pub async fn setup_exists(
     car: String,
     track: String,
     filename: String,
+    library: Option<String>,
     state: State<'_, Arc<AppStateManager>>,
 ) -> Result<bool, AccError> {
-    match state.read_setup(&car, &track, &filename).await {
+    let library = state.library(library.as_deref()).await?;
+    match library.read_setup(&car, &track, &filename).await {
         Ok(_) => Ok(true),
         Err(AccError::FileNotFound { .. }) => Ok(false),
         Err(e) => Err(e),
     }
 }
 
-/// Validate setup content without saving
+/// Validate setup content without saving. Fails with every violation found
+/// if the content is malformed or out of range for the car, otherwise returns
+/// the car names that were resolved through an alias.
 #[tauri::command]
-pub async fn validate_setup(car: String, content: JsonValue) -> Result<bool, AccError> {
-    // Basic validation checks
-    if !content.is_object() {
-        return Err(AccError::SetupValidationFailed {
-            reason: "Setup content must be a JSON object".to_string(),
-        });
+pub async fn validate_setup(car: String, content: JsonValue) -> Result<Vec<AliasMatch>, AccError> {
+    let SetupValidation {
+        violations,
+        aliases,
+    } = validation::validate_setup(&car, &content);
+
+    if violations.is_empty() {
+        Ok(aliases)
+    } else {
+        info!(
+            "Setup for {} has {} invalid parameters",
+            car,
+            violations.len()
+        );
+        Err(AccError::InvalidSetupParameters { violations })
     }
+}
 
-    let obj = content.as_object().unwrap();
+/// Get the legal setup parameter ranges for a car
+#[tauri::command]
+pub async fn get_setup_limits(car: String) -> Result<Vec<ParameterLimit>, AccError> {
+    data::get_setup_limits(&car).ok_or(AccError::InvalidCarId { car_id: car })
+}
+
+/// Get the click-to-physical conversions of a car's setup parameters
+#[tauri::command]
+pub async fn get_parameter_conversions(car: String) -> Result<Vec<ParameterConversion>, AccError> {
+    data::get_parameter_conversions(&car).ok_or(AccError::InvalidCarId { car_id: car })
+}
 
-    // Check for required fields
-    if !obj.contains_key("carName") {
-        return Err(AccError::MissingRequiredField {
-            field: "carName".to_string(),
-        });
+/// Get a setup with every parameter converted from clicks to physical units
+#[tauri::command]
+pub async fn get_setup_physical_values(
+    car: String,
+    track: String,
+    filename: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<PhysicalSetup, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Getting physical values of setup: {}/{}/{}",
+        car, track, filename
+    );
+    match library
+        .read_setup_physical_values(&car, &track, &filename)
+        .await
+    {
+        Ok(physical) => {
+            info!(
+                "Converted {} parameters of {}/{}/{}",
+                physical.parameters.len(),
+                car,
+                track,
+                filename
+            );
+            Ok(physical)
+        }
+        Err(e) => {
+            error!(
+                "Failed to get physical values of {}/{}/{}: {}",
+                car, track, filename, e
+            );
+            Err(e)
+        }
     }
+}
 
-    if !obj.contains_key("basicSetup") {
-        return Err(AccError::MissingRequiredField {
-            field: "basicSetup".to_string(),
-        });
+/// Calculate start fuel, pit stops and stints for a race with a setup, and
+/// optionally write the result into the setup's strategy block
+#[tauri::command]
+pub async fn calculate_fuel_strategy(
+    car: String,
+    track: String,
+    filename: String,
+    request: FuelStrategyRequest,
+    apply: Option<bool>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<FuelStrategy, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    let apply = apply.unwrap_or(false);
+    info!(
+        "Calculating fuel strategy for {}/{}/{}: {:?}",
+        car, track, filename, request
+    );
+    match library
+        .calculate_fuel_strategy(&car, &track, &filename, &request, apply)
+        .await
+    {
+        Ok(strategy) => {
+            info!(
+                "Planned {} laps with {} stops{}",
+                strategy.total_laps,
+                strategy.pit_stops,
+                if apply { ", saved to setup" } else { "" }
+            );
+            Ok(strategy)
+        }
+        Err(e) => {
+            error!(
+                "Failed to calculate fuel strategy for {}/{}/{}: {}",
+                car, track, filename, e
+            );
+            Err(e)
+        }
     }
+}
+
+/// Convert a physical value of a setup parameter back to the nearest click.
+/// Fails if the parameter has no conversion data or the click is outside the
+/// car's limits.
+#[tauri::command]
+pub async fn physical_value_to_clicks(
+    car: String,
+    path: String,
+    value: f64,
+) -> Result<i64, AccError> {
+    units::to_clicks(&car, &path, value)
+}
 
-    if !obj.contains_key("advancedSetup") {
-        return Err(AccError::MissingRequiredField {
-            field: "advancedSetup".to_string(),
-        });
+/// Search setups by text, metadata and parameter predicates, best matches first
+#[tauri::command]
+pub async fn search_setups(
+    query: SetupQuery,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<SearchResult>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Searching setups: {:?}", query);
+    match library.search_setups(&query).await {
+        Ok(results) => {
+            info!("Search returned {} setups", results.len());
+            Ok(results)
+        }
+        Err(e) => {
+            error!("Failed to search setups: {}", e);
+            Err(e)
+        }
     }
+}
 
-    // Validate car name if provided
-    let cars = data::get_cars();
-    if let Some(car_data) = cars.get(&car) {
-        if let Some(car_name) = obj.get("carName").and_then(|v| v.as_str()) {
-            if car_name != car_data.id {
-                return Err(AccError::CarNameMismatch {
-                    json_car: car_name.to_string(),
-                    folder_car: car_data.id.clone(),
-                });
+/// Compare two setups and return the list of changed parameters
+#[tauri::command]
+pub async fn diff_setups(
+    left: SetupRef,
+    right: SetupRef,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<ParameterChange>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Diffing setups: {}/{}/{} -> {}/{}/{}",
+        left.car, left.track, left.filename, right.car, right.track, right.filename
+    );
+
+    let old_setup = library
+        .read_setup(&left.car, &left.track, &left.filename)
+        .await?;
+    let new_setup = library
+        .read_setup(&right.car, &right.track, &right.filename)
+        .await?;
+
+    match diff::diff_setups(&old_setup, &new_setup) {
+        Ok(changes) => {
+            info!("Found {} changed parameters", changes.len());
+            Ok(changes)
+        }
+        Err(e) => {
+            error!("Failed to diff setups: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// List the stored revisions of a setup file
+#[tauri::command]
+pub async fn list_setup_history(
+    car: String,
+    track: String,
+    filename: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<SetupRevision>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Listing history: {}/{}/{}", car, track, filename);
+    match library.list_setup_history(&car, &track, &filename).await {
+        Ok(revisions) => {
+            info!("Found {} revisions", revisions.len());
+            Ok(revisions)
+        }
+        Err(e) => {
+            error!(
+                "Failed to list history {}/{}/{}: {}",
+                car, track, filename, e
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Get the content of a stored setup revision
+#[tauri::command]
+pub async fn get_setup_revision(
+    car: String,
+    track: String,
+    filename: String,
+    revision_id: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<SetupFile, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Getting revision {} of {}/{}/{}",
+        revision_id, car, track, filename
+    );
+    match library
+        .read_setup_revision(&car, &track, &filename, &revision_id)
+        .await
+    {
+        Ok(setup) => Ok(setup),
+        Err(e) => {
+            error!(
+                "Failed to get revision {} of {}/{}/{}: {}",
+                revision_id, car, track, filename, e
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Diff two revisions of a setup, or a revision against the current file
+#[tauri::command]
+pub async fn diff_setup_revisions(
+    car: String,
+    track: String,
+    filename: String,
+    from_revision: String,
+    to_revision: Option<String>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<ParameterChange>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Diffing revisions of {}/{}/{}: {} -> {}",
+        car,
+        track,
+        filename,
+        from_revision,
+        to_revision.as_deref().unwrap_or("current")
+    );
+    match library
+        .diff_setup_revisions(
+            &car,
+            &track,
+            &filename,
+            &from_revision,
+            to_revision.as_deref(),
+        )
+        .await
+    {
+        Ok(changes) => {
+            info!("Found {} changed parameters", changes.len());
+            Ok(changes)
+        }
+        Err(e) => {
+            error!("Failed to diff revisions: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Restore a stored revision over the current setup file
+#[tauri::command]
+pub async fn restore_setup_revision(
+    car: String,
+    track: String,
+    filename: String,
+    revision_id: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<(), AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Restoring revision {} of {}/{}/{}",
+        revision_id, car, track, filename
+    );
+    match library
+        .restore_setup_revision(&car, &track, &filename, &revision_id)
+        .await
+    {
+        Ok(()) => {
+            info!(
+                "Successfully restored revision {} of {}/{}/{}",
+                revision_id, car, track, filename
+            );
+            Ok(())
+        }
+        Err(e) => {
+            error!(
+                "Failed to restore revision {} of {}/{}/{}: {}",
+                revision_id, car, track, filename, e
+            );
+            Err(e)
+        }
+    }
+}
+
+/// List the setups currently in the trash
+#[tauri::command]
+pub async fn list_trash(
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<TrashEntry>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Listing trash");
+    match library.list_trash().await {
+        Ok(entries) => {
+            info!("Found {} items in trash", entries.len());
+            Ok(entries)
+        }
+        Err(e) => {
+            error!("Failed to list trash: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Restore a trashed setup to its original location
+#[tauri::command]
+pub async fn restore_from_trash(
+    id: String,
+    overwrite: Option<bool>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<TrashEntry, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Restoring trash item: {}", id);
+    match library
+        .restore_from_trash(&id, overwrite.unwrap_or(false))
+        .await
+    {
+        Ok(entry) => {
+            info!(
+                "Successfully restored {}/{}/{}",
+                entry.car, entry.track, entry.filename
+            );
+            Ok(entry)
+        }
+        Err(e) => {
+            error!("Failed to restore trash item {}: {}", id, e);
+            Err(e)
+        }
+    }
+}
+
+/// Permanently delete everything in the trash
+#[tauri::command]
+pub async fn empty_trash(
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<usize, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Emptying trash");
+    match library.empty_trash().await {
+        Ok(removed) => Ok(removed),
+        Err(e) => {
+            error!("Failed to empty trash: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Get the trash retention window in days
+#[tauri::command]
+pub async fn get_trash_retention_days(
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<u32, AccError> {
+    Ok(state.get_trash_retention_days().await)
+}
+
+/// Set the trash retention window in days (0 keeps items forever)
+#[tauri::command]
+pub async fn set_trash_retention_days(
+    days: u32,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<(), AccError> {
+    info!("Setting trash retention to {} days", days);
+    match state.set_trash_retention_days(days).await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            error!("Failed to set trash retention: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Copy a setup to another car/track and/or filename
+#[tauri::command]
+pub async fn copy_setup(
+    source: SetupRef,
+    target: SetupRef,
+    overwrite: Option<bool>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<SetupTransfer, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Copying setup: {}/{}/{} -> {}/{}/{}",
+        source.car, source.track, source.filename, target.car, target.track, target.filename
+    );
+    match library
+        .copy_setup(&source, &target, overwrite.unwrap_or(false))
+        .await
+    {
+        Ok(transfer) => {
+            info!(
+                "Successfully copied setup to {}/{}/{}",
+                transfer.car, transfer.track, transfer.filename
+            );
+            Ok(transfer)
+        }
+        Err(e) => {
+            error!("Failed to copy setup: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Move a setup to another car/track and/or filename
+#[tauri::command]
+pub async fn move_setup(
+    source: SetupRef,
+    target: SetupRef,
+    overwrite: Option<bool>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<SetupTransfer, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Moving setup: {}/{}/{} -> {}/{}/{}",
+        source.car, source.track, source.filename, target.car, target.track, target.filename
+    );
+    match library
+        .move_setup(&source, &target, overwrite.unwrap_or(false))
+        .await
+    {
+        Ok(transfer) => {
+            info!(
+                "Successfully moved setup to {}/{}/{}",
+                transfer.car, transfer.track, transfer.filename
+            );
+            Ok(transfer)
+        }
+        Err(e) => {
+            error!("Failed to move setup: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Rename a setup within its car/track folder
+#[tauri::command]
+pub async fn rename_setup(
+    car: String,
+    track: String,
+    filename: String,
+    new_filename: String,
+    overwrite: Option<bool>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<SetupTransfer, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!(
+        "Renaming setup: {}/{}/{} -> {}",
+        car, track, filename, new_filename
+    );
+    match library
+        .rename_setup(
+            &car,
+            &track,
+            &filename,
+            &new_filename,
+            overwrite.unwrap_or(false),
+        )
+        .await
+    {
+        Ok(transfer) => {
+            info!("Successfully renamed setup to {}", transfer.filename);
+            Ok(transfer)
+        }
+        Err(e) => {
+            error!(
+                "Failed to rename setup {}/{}/{}: {}",
+                car, track, filename, e
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Import setup files or folders, returning a per-file report. With
+/// `commit` unset nothing is written and the report is a preview plan.
+#[tauri::command]
+pub async fn import_setups(
+    paths: Vec<String>,
+    track: Option<String>,
+    overwrite: Option<bool>,
+    commit: Option<bool>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<ImportReport, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Importing setups from {} paths", paths.len());
+    let paths: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+    match library
+        .import_setups(
+            &paths,
+            track.as_deref(),
+            overwrite.unwrap_or(false),
+            commit.unwrap_or(false),
+        )
+        .await
+    {
+        Ok(report) => {
+            info!("Import report contains {} files", report.items.len());
+            Ok(report)
+        }
+        Err(e) => {
+            error!("Failed to import setups: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Export the selected setups into a zip pack
+#[tauri::command]
+pub async fn export_setups(
+    selection: Vec<SetupRef>,
+    dest: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<usize, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Exporting {} setups to {}", selection.len(), dest);
+    match library
+        .export_setups(&selection, &PathBuf::from(&dest))
+        .await
+    {
+        Ok(count) => {
+            info!("Successfully exported {} setups", count);
+            Ok(count)
+        }
+        Err(e) => {
+            error!("Failed to export setups to {}: {}", dest, e);
+            Err(e)
+        }
+    }
+}
+
+/// Import a zip setup pack, returning a per-file report. With `commit`
+/// unset nothing is written and the report is a preview plan.
+#[tauri::command]
+pub async fn import_setup_pack(
+    path: String,
+    overwrite: Option<bool>,
+    commit: Option<bool>,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<ImportReport, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Importing setup pack: {}", path);
+    match library
+        .import_setup_pack(
+            &PathBuf::from(&path),
+            overwrite.unwrap_or(false),
+            commit.unwrap_or(false),
+        )
+        .await
+    {
+        Ok(report) => {
+            info!("Setup pack report contains {} entries", report.items.len());
+            Ok(report)
+        }
+        Err(e) => {
+            error!("Failed to import setup pack {}: {}", path, e);
+            Err(e)
+        }
+    }
+}
+
+/// Get the file watcher debounce window in milliseconds
+#[tauri::command]
+pub async fn get_watcher_debounce_ms(
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<u64, AccError> {
+    Ok(state.get_config().await.watcher_debounce_ms)
+}
+
+/// Set the file watcher debounce window in milliseconds
+#[tauri::command]
+pub async fn set_watcher_debounce_ms(
+    debounce_ms: u64,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<(), AccError> {
+    info!("Setting watcher debounce to {} ms", debounce_ms);
+    match state.set_watcher_debounce_ms(debounce_ms).await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            error!("Failed to set watcher debounce: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Get the persisted app configuration
+#[tauri::command]
+pub async fn get_config(state: State<'_, Arc<AppStateManager>>) -> Result<AppConfig, AccError> {
+    Ok(state.get_config().await)
+}
+
+/// Update and persist the app configuration. Libraries are left unchanged;
+/// use the library commands to manage them.
+#[tauri::command]
+pub async fn update_config(
+    config: AppConfig,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<AppConfig, AccError> {
+    info!("Updating app configuration");
+    match state.update_config(config).await {
+        Ok(updated) => Ok(updated),
+        Err(e) => {
+            error!("Failed to update app configuration: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// List the configured setup libraries
+#[tauri::command]
+pub async fn list_libraries(
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<LibraryConfig>, AccError> {
+    let libraries = state.list_libraries().await;
+    info!("Found {} libraries", libraries.len());
+    Ok(libraries)
+}
+
+/// Register an existing folder as a setup library and start watching it
+#[tauri::command]
+pub async fn add_library(
+    name: String,
+    path: String,
+    live: Option<bool>,
+    state: State<'_, Arc<AppStateManager>>,
+    watcher: State<'_, SharedWatcher>,
+    app_handle: AppHandle,
+) -> Result<LibraryConfig, AccError> {
+    info!("Adding library '{}' at {}", name, path);
+    let path_buf = PathBuf::from(&path);
+    match state
+        .add_library(&name, path_buf.clone(), live.unwrap_or(false))
+        .await
+    {
+        Ok((library_config, library)) => {
+            if let Err(e) = library.refresh_folder_structure().await {
+                error!("Failed to scan library {}: {}", library_config.id, e);
             }
+            match FileWatcher::new(&path_buf, library, app_handle) {
+                Ok(file_watcher) => {
+                    watcher
+                        .lock()
+                        .await
+                        .insert(library_config.id.clone(), file_watcher);
+                }
+                Err(e) => error!("Failed to watch library {}: {}", library_config.id, e),
+            }
+            Ok(library_config)
+        }
+        Err(e) => {
+            error!("Failed to add library '{}': {}", name, e);
+            Err(e)
+        }
+    }
+}
+
+/// Stop managing a setup library. The folder and its files are kept.
+#[tauri::command]
+pub async fn remove_library(
+    id: String,
+    state: State<'_, Arc<AppStateManager>>,
+    watcher: State<'_, SharedWatcher>,
+) -> Result<(), AccError> {
+    info!("Removing library {}", id);
+    match state.remove_library(&id).await {
+        Ok(()) => {
+            watcher.lock().await.remove(&id);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to remove library {}: {}", id, e);
+            Err(e)
+        }
+    }
+}
+
+/// Mark a library as the live ACC setups folder
+#[tauri::command]
+pub async fn set_live_library(
+    id: String,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<(), AccError> {
+    info!("Setting live library: {}", id);
+    match state.set_live_library(&id).await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            error!("Failed to set live library {}: {}", id, e);
+            Err(e)
         }
     }
+}
+
+/// Copy a setup from one library into another
+#[tauri::command]
+pub async fn copy_setup_between_libraries(
+    source_library: String,
+    source: SetupRef,
+    target_library: String,
+    target: SetupRef,
+    overwrite: Option<bool>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<SetupTransfer, AccError> {
+    info!(
+        "Copying setup: {}:{}/{}/{} -> {}:{}/{}/{}",
+        source_library,
+        source.car,
+        source.track,
+        source.filename,
+        target_library,
+        target.car,
+        target.track,
+        target.filename
+    );
+    match state
+        .copy_between_libraries(
+            &source_library,
+            &source,
+            &target_library,
+            &target,
+            overwrite.unwrap_or(false),
+        )
+        .await
+    {
+        Ok(transfer) => {
+            info!(
+                "Successfully copied setup to {}:{}/{}/{}",
+                target_library, transfer.car, transfer.track, transfer.filename
+            );
+            Ok(transfer)
+        }
+        Err(e) => {
+            error!("Failed to copy setup between libraries: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// List the tags used in a library with their usage counts
+#[tauri::command]
+pub async fn list_tags(
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<Vec<TagUsage>, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Listing tags");
+    match library.list_tags().await {
+        Ok(tags) => {
+            info!("Found {} tags", tags.len());
+            Ok(tags)
+        }
+        Err(e) => {
+            error!("Failed to list tags: {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Rename a tag in every setup of a library
+#[tauri::command]
+pub async fn rename_tag(
+    from: String,
+    to: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<TagUpdateReport, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Renaming tag '{}' to '{}'", from, to);
+    match library.rename_tag(&from, &to).await {
+        Ok(report) => {
+            info!("Renamed tag in {} setups", report.changed.len());
+            Ok(report)
+        }
+        Err(e) => {
+            error!("Failed to rename tag '{}': {}", from, e);
+            Err(e)
+        }
+    }
+}
 
-    Ok(true)
+/// Merge several tags into one in every setup of a library
+#[tauri::command]
+pub async fn merge_tags(
+    sources: Vec<String>,
+    target: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<TagUpdateReport, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Merging tags {:?} into '{}'", sources, target);
+    match library.merge_tags(&sources, &target).await {
+        Ok(report) => {
+            info!("Merged tags in {} setups", report.changed.len());
+            Ok(report)
+        }
+        Err(e) => {
+            error!("Failed to merge tags into '{}': {}", target, e);
+            Err(e)
+        }
+    }
+}
+
+/// Remove a tag from every setup of a library
+#[tauri::command]
+pub async fn delete_tag(
+    tag: String,
+    library: Option<String>,
+    state: State<'_, Arc<AppStateManager>>,
+) -> Result<TagUpdateReport, AccError> {
+    let library = state.library(library.as_deref()).await?;
+    info!("Deleting tag '{}'", tag);
+    match library.delete_tag(&tag).await {
+        Ok(report) => {
+            info!("Deleted tag from {} setups", report.changed.len());
+            Ok(report)
+        }
+        Err(e) => {
+            error!("Failed to delete tag '{}': {}", tag, e);
+            Err(e)
+        }
+    }
 }
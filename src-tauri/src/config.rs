@@ -0,0 +1,236 @@
+use crate::errors::{AccError, AccResult};
+use crate::files;
+use crate::state::DEFAULT_WATCHER_DEBOUNCE_MS;
+use crate::trash::DEFAULT_RETENTION_DAYS;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+/// Current version of the config file format
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Folder name of the app inside the platform config directory
+const CONFIG_DIR_NAME: &str = "accsm";
+
+const CONFIG_FILE_NAME: &str = "config.json";
+
+const CATALOG_FILE_NAME: &str = "catalog.json";
+
+/// Id of the library created for the ACC setups folder on first start
+pub const DEFAULT_LIBRARY_ID: &str = "default";
+
+/// Display name of the library created on first start
+pub const DEFAULT_LIBRARY_NAME: &str = "ACC Setups";
+
+/// UI preferences persisted for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiPreferences {
+    pub theme: String,
+    /// Any other frontend preference, stored as-is
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, Value>,
+}
+
+impl Default for UiPreferences {
+    fn default() -> Self {
+        Self {
+            theme: "system".to_string(),
+            other_fields: HashMap::new(),
+        }
+    }
+}
+
+/// A named setups folder managed by the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryConfig {
+    pub id: String,
+    pub name: String,
+    pub path: PathBuf,
+    /// Whether this is the folder ACC itself reads setups from
+    pub live: bool,
+}
+
+/// Persisted application configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub version: u32,
+    pub libraries: Vec<LibraryConfig>,
+    pub ui: UiPreferences,
+    /// Tags applied to setups that are saved without any ACCSMData
+    pub default_tags: Vec<String>,
+    /// Setup type used when a saved setup doesn't specify one
+    pub default_setup_type: String,
+    pub trash_retention_days: u32,
+    pub watcher_debounce_ms: u64,
+    /// Keys written by newer versions of the app, kept so they survive a save
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, Value>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            libraries: Vec::new(),
+            ui: UiPreferences::default(),
+            default_tags: Vec::new(),
+            default_setup_type: "race".to_string(),
+            trash_retention_days: DEFAULT_RETENTION_DAYS,
+            watcher_debounce_ms: DEFAULT_WATCHER_DEBOUNCE_MS,
+            other_fields: HashMap::new(),
+        }
+    }
+}
+
+/// Location of the config file inside the platform config directory
+pub fn config_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Location of the user's car and track catalog overrides
+pub fn catalog_override_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CATALOG_FILE_NAME))
+}
+
+/// Load the config file, migrating older versions. Never fails: a missing
+/// file yields the defaults, and an unreadable one is backed up first.
+pub fn load(path: &Path) -> AppConfig {
+    if !path.exists() {
+        info!("No config file at {:?}, using defaults", path);
+        return AppConfig::default();
+    }
+
+    match read_config(path) {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Failed to load config file {:?}: {}", path, e);
+            let backup_path = path.with_extension("json.bak");
+            if let Err(e) = fs::rename(path, &backup_path) {
+                warn!("Failed to back up broken config file: {}", e);
+            } else {
+                warn!("Backed up broken config file to {:?}", backup_path);
+            }
+            AppConfig::default()
+        }
+    }
+}
+
+fn read_config(path: &Path) -> AccResult<AppConfig> {
+    let content = fs::read_to_string(path).map_err(|e| AccError::IoError {
+        message: format!("Failed to read config file: {}", e),
+    })?;
+    let value: Value = serde_json::from_str(&content)?;
+    let Value::Object(mut obj) = value else {
+        return Err(AccError::SerializationError {
+            message: "Config file must contain a JSON object".to_string(),
+        });
+    };
+
+    let version =
+        obj.get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| AccError::SerializationError {
+                message: "Config file has no version".to_string(),
+            })? as u32;
+
+    if version > CONFIG_VERSION {
+        // Keep the newer version number so saving doesn't claim a downgrade
+        warn!(
+            "Config file version {} is newer than supported version {}",
+            version, CONFIG_VERSION
+        );
+    } else {
+        migrate(&mut obj, version);
+    }
+
+    Ok(serde_json::from_value(Value::Object(obj))?)
+}
+
+/// Upgrade a config object from `version` to `CONFIG_VERSION` one step at a
+/// time. Version 1 is the first released format, so there are no steps yet.
+fn migrate(obj: &mut Map<String, Value>, _version: u32) {
+    obj.insert("version".to_string(), Value::from(CONFIG_VERSION));
+}
+
+/// Write the config file atomically, creating its folder if needed
+pub fn save(path: &Path, config: &AppConfig) -> AccResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AccError::DirectoryCreationFailed {
+            path: parent.to_string_lossy().to_string(),
+            error: e.to_string(),
+        })?;
+    }
+
+    let json_string = serde_json::to_string_pretty(config)?;
+    files::write_atomic(path, json_string.as_bytes()).map_err(|e| AccError::FileWriteFailed {
+        path: path.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })
+}
+
+/// The shared configuration, persisted on every change
+pub struct ConfigStore {
+    config: RwLock<AppConfig>,
+    path: Option<PathBuf>,
+}
+
+impl ConfigStore {
+    /// Wrap a loaded config; changes are written to `path` when one is given
+    pub fn new(config: AppConfig, path: Option<PathBuf>) -> Self {
+        Self {
+            config: RwLock::new(config),
+            path,
+        }
+    }
+
+    /// Borrow the current configuration
+    pub async fn read(&self) -> RwLockReadGuard<'_, AppConfig> {
+        self.config.read().await
+    }
+
+    /// Apply a change to the configuration and persist it. The change is only
+    /// kept in memory once it has been saved.
+    pub async fn update<F>(&self, change: F) -> AccResult<()>
+    where
+        F: FnOnce(&mut AppConfig),
+    {
+        let mut config = self.config.write().await;
+        let mut updated = config.clone();
+        change(&mut updated);
+
+        if let Some(path) = &self.path {
+            save(path, &updated)?;
+            debug!("Saved configuration to {:?}", path);
+        }
+        *config = updated;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_failed_save_leaves_the_config_unchanged() {
+        // A file where the config folder should be makes every save fail
+        let blocker = std::env::temp_dir().join(format!("accsm_config_{}", std::process::id()));
+        fs::write(&blocker, "").unwrap();
+        let store = ConfigStore::new(AppConfig::default(), Some(blocker.join(CONFIG_FILE_NAME)));
+
+        let result = store.update(|config| config.trash_retention_days = 1).await;
+        assert!(result.is_err());
+        assert_eq!(
+            store.read().await.trash_retention_days,
+            DEFAULT_RETENTION_DAYS
+        );
+
+        fs::remove_file(&blocker).unwrap();
+    }
+}
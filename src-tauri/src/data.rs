@@ -1,113 +1,320 @@
-use crate::models::{Car, Track};
+use crate::config;
+use crate::models::{AliasMatch, Car, ParameterConversion, ParameterLimit, Track};
+use crate::validation;
+use log::{info, warn};
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
 
-/// Static data for ACC cars
-pub fn get_cars() -> HashMap<String, Car> {
-    let mut cars = HashMap::new();
-
-    let car_data = vec![
-        ("audi_r8_evo", "Audi R8 LMS Evo"),
-        ("audi_r8_evo_ii", "Audi R8 LMS Evo II"),
-        ("bmw_m4_gt3", "BMW M4 GT3"),
-        (
-            "bentley_continental_gt3_2018",
-            "Bentley Continental GT3 2018",
-        ),
-        ("ferrari_488_gt3", "Ferrari 488 GT3"),
-        ("ferrari_488_gt3_evo", "Ferrari 488 GT3 Evo"),
-        ("lamborghini_huracan_gt3", "Lamborghini Huracán GT3"),
-        ("lamborghini_huracan_gt3_evo", "Lamborghini Huracán GT3 Evo"),
-        (
-            "lamborghini_huracan_gt3_evo2",
-            "Lamborghini Huracán GT3 Evo2",
-        ),
-        ("mclaren_720s_gt3", "McLaren 720S GT3"),
-        ("mercedes_amg_gt3", "Mercedes-AMG GT3"),
-        ("mercedes_amg_gt3_evo", "Mercedes-AMG GT3 Evo"),
-        ("porsche_991_gt3_r", "Porsche 991 GT3 R"),
-        ("porsche_991ii_gt3_r", "Porsche 991.2 GT3 R"),
-        ("nissan_gt_r_gt3_2018", "Nissan GT-R Nismo GT3"),
-        ("lexus_rc_f_gt3", "Lexus RC F GT3"),
-        ("honda_nsx_gt3", "Honda NSX GT3"),
-        ("honda_nsx_gt3_evo", "Honda NSX GT3 Evo"),
-        ("alpine_a110_gt4", "Alpine A110 GT4"),
-        ("aston_martin_vantage_gt4", "Aston Martin Vantage GT4"),
-        ("bmw_m4_gt4", "BMW M4 GT4"),
-        ("chevrolet_camaro_gt4r", "Chevrolet Camaro GT4.R"),
-        ("ginetta_g55_gt4", "Ginetta G55 GT4"),
-        ("ktm_xbow_gt4", "KTM X-Bow GT4"),
-        ("maserati_mc_gt4", "Maserati MC GT4"),
-        ("mclaren_570s_gt4", "McLaren 570S GT4"),
-        ("mercedes_amg_gt4", "Mercedes-AMG GT4"),
-        ("porsche_718_cayman_gt4_clubsport", "Porsche 718 Cayman GT4"),
-    ];
-
-    for (id, pretty_name) in car_data {
-        cars.insert(
-            id.to_string(),
-            Car {
-                id: id.to_string(),
-                pretty_name: pretty_name.to_string(),
-            },
+/// Car and track catalog shipped with the app
+const BUNDLED_CATALOG: &str = include_str!("../data/catalog.json");
+
+/// Car and track catalog, as stored in the bundled and override files
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Catalog {
+    cars: Vec<Car>,
+    tracks: Vec<Track>,
+    /// Click-to-physical conversions shared by every car
+    conversions: Vec<ParameterConversion>,
+    #[serde(skip)]
+    car_index: CatalogIndex,
+    #[serde(skip)]
+    track_index: CatalogIndex,
+}
+
+impl Catalog {
+    /// Merge an override catalog: entries with a known id or conversion
+    /// target replace the bundled ones, new ones are appended
+    fn merge(&mut self, overrides: Catalog) {
+        for car in overrides.cars {
+            match self.cars.iter_mut().find(|existing| existing.id == car.id) {
+                Some(existing) => *existing = car,
+                None => self.cars.push(car),
+            }
+        }
+        for track in overrides.tracks {
+            match self
+                .tracks
+                .iter_mut()
+                .find(|existing| existing.id == track.id)
+            {
+                Some(existing) => *existing = track,
+                None => self.tracks.push(track),
+            }
+        }
+        for conversion in overrides.conversions {
+            match self
+                .conversions
+                .iter_mut()
+                .find(|existing| same_target(existing, &conversion))
+            {
+                Some(existing) => *existing = conversion,
+                None => self.conversions.push(conversion),
+            }
+        }
+    }
+
+    /// Build the lookup indexes once all entries are in place
+    fn build_indexes(&mut self) {
+        self.car_index = CatalogIndex::build(
+            self.cars
+                .iter()
+                .map(|car| (car.id.as_str(), car.aliases.as_slice())),
         );
+        self.track_index = CatalogIndex::build(
+            self.tracks
+                .iter()
+                .map(|track| (track.id.as_str(), track.aliases.as_slice())),
+        );
+    }
+}
+
+/// Positions of catalog entries by exact id, and by lowercase id or alias
+#[derive(Debug, Default)]
+struct CatalogIndex {
+    by_id: HashMap<String, usize>,
+    /// Lowercase names, with the alias they were indexed for (`None` for ids)
+    by_name: HashMap<String, (usize, Option<String>)>,
+}
+
+impl CatalogIndex {
+    /// Index `(id, aliases)` pairs in catalog order. An alias never shadows
+    /// the id of another entry.
+    fn build<'a>(entries: impl Iterator<Item = (&'a str, &'a [String])>) -> Self {
+        let entries: Vec<_> = entries.collect();
+        let mut index = Self::default();
+
+        for (position, (id, _)) in entries.iter().enumerate() {
+            index.by_id.insert(id.to_string(), position);
+            index
+                .by_name
+                .entry(id.to_lowercase())
+                .or_insert((position, None));
+        }
+        for (position, (_, aliases)) in entries.iter().enumerate() {
+            for alias in aliases.iter() {
+                index
+                    .by_name
+                    .entry(alias.to_lowercase())
+                    .or_insert_with(|| (position, Some(alias.clone())));
+            }
+        }
+
+        index
+    }
+
+    fn get(&self, id: &str) -> Option<usize> {
+        self.by_id.get(id).copied()
     }
 
-    cars
+    /// Find an entry by exact id, then by id in any case or by alias.
+    /// Returns the alias that matched, if any.
+    fn lookup(&self, name: &str) -> Option<(usize, Option<&str>)> {
+        if let Some(position) = self.get(name) {
+            return Some((position, None));
+        }
+        self.by_name
+            .get(&name.to_lowercase())
+            .map(|(position, alias)| (*position, alias.as_deref()))
+    }
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// The catalog, loaded on first use and shared for the rest of the process
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(load_catalog)
+}
+
+/// Load the bundled catalog and apply the user's override file, if any.
+/// A broken override file is skipped so the app still starts.
+fn load_catalog() -> Catalog {
+    let mut catalog: Catalog =
+        serde_json::from_str(BUNDLED_CATALOG).expect("bundled catalog is valid JSON");
+
+    if let Some(path) = config::catalog_override_path().filter(|path| path.exists()) {
+        let overrides = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<Catalog>(&content).map_err(|e| e.to_string())
+            });
+        match overrides {
+            Ok(overrides) => {
+                info!(
+                    "Loaded {} car and {} track overrides from {:?}",
+                    overrides.cars.len(),
+                    overrides.tracks.len(),
+                    path
+                );
+                catalog.merge(overrides);
+            }
+            Err(e) => warn!("Ignoring invalid catalog override file {:?}: {}", path, e),
+        }
+    }
+
+    catalog.build_indexes();
+    catalog
+}
+
+/// All cars of the catalog
+pub fn cars() -> &'static [Car] {
+    &catalog().cars
+}
+
+/// All tracks of the catalog
+pub fn tracks() -> &'static [Track] {
+    &catalog().tracks
+}
+
+/// Look up a car by its exact id
+pub fn get_car(id: &str) -> Option<&'static Car> {
+    let catalog = catalog();
+    catalog
+        .car_index
+        .get(id)
+        .map(|position| &catalog.cars[position])
 }
 
-/// Static data for ACC tracks
+/// Look up a track by its exact id
+pub fn get_track(id: &str) -> Option<&'static Track> {
+    let catalog = catalog();
+    catalog
+        .track_index
+        .get(id)
+        .map(|position| &catalog.tracks[position])
+}
+
+/// Look up a car by its id in any case, or by one of its aliases
+pub fn lookup_car(name: &str) -> Option<&'static Car> {
+    resolve_car(name).map(|(car, _)| car)
+}
+
+/// Look up a track by its id in any case, or by one of its aliases
+pub fn lookup_track(name: &str) -> Option<&'static Track> {
+    resolve_track(name).map(|(track, _)| track)
+}
+
+/// Resolve a folder name or `carName` value to a car. Also returns how the
+/// name was matched unless it is the exact id.
+pub fn resolve_car(name: &str) -> Option<(&'static Car, Option<AliasMatch>)> {
+    let catalog = catalog();
+    let (position, alias) = catalog.car_index.lookup(name)?;
+    let car = &catalog.cars[position];
+    Some((car, alias_match(name, &car.id, alias)))
+}
+
+/// Resolve a folder name to a track. Also returns how the name was matched
+/// unless it is the exact id.
+pub fn resolve_track(name: &str) -> Option<(&'static Track, Option<AliasMatch>)> {
+    let catalog = catalog();
+    let (position, alias) = catalog.track_index.lookup(name)?;
+    let track = &catalog.tracks[position];
+    Some((track, alias_match(name, &track.id, alias)))
+}
+
+fn alias_match(name: &str, id: &str, alias: Option<&str>) -> Option<AliasMatch> {
+    (name != id).then(|| AliasMatch {
+        name: name.to_string(),
+        id: id.to_string(),
+        alias: alias.map(str::to_string),
+    })
+}
+
+/// Catalog cars keyed by id
+pub fn get_cars() -> HashMap<String, Car> {
+    cars()
+        .iter()
+        .map(|car| (car.id.clone(), car.clone()))
+        .collect()
+}
+
+/// Catalog tracks keyed by id
 pub fn get_tracks() -> HashMap<String, Track> {
-    let mut tracks = HashMap::new();
-
-    let track_data = vec![
-        ("Barcelona", "Circuit de Barcelona-Catalunya"),
-        ("brands_hatch", "Brands Hatch Circuit"),
-        ("cota", "Circuit of The Americas"),
-        ("donington", "Donington Park"),
-        ("Hungaroring", "Hungaroring"),
-        ("Imola", "Autodromo Enzo e Dino Ferrari"),
-        ("indianapolis", "Indianapolis Motor Speedway"),
-        ("Kyalami", "Kyalami Grand Prix Circuit"),
-        ("Laguna_Seca", "WeatherTech Raceway Laguna Seca"),
-        ("misano", "Misano World Circuit Marco Simoncelli"),
-        ("monza", "Autodromo Nazionale Monza"),
-        ("mount_panorama", "Mount Panorama Circuit"),
-        ("nurburgring", "Nürburgring-Nordschleife"),
-        ("oulton_park", "Oulton Park"),
-        ("Paul_Ricard", "Circuit Paul Ricard"),
-        ("Silverstone", "Silverstone Circuit"),
-        ("snetterton", "Snetterton Circuit"),
-        ("Spa", "Circuit de Spa-Francorchamps"),
-        ("Suzuka", "Suzuka Circuit"),
-        ("Valencia", "Circuit Ricardo Tormo"),
-        ("watkins_glen", "Watkins Glen International"),
-        ("Zandvoort", "Circuit Zandvoort"),
-        ("Zolder", "Circuit Zolder"),
-        ("red_bull_ring", "Red Bull Ring"),
-        ("magny_cours", "Circuit de Nevers Magny-Cours"),
-    ];
-
-    for (id, pretty_name) in track_data {
-        tracks.insert(
-            id.to_string(),
-            Track {
-                id: id.to_string(),
-                pretty_name: pretty_name.to_string(),
-            },
-        );
+    tracks()
+        .iter()
+        .map(|track| (track.id.clone(), track.clone()))
+        .collect()
+}
+
+/// Fuel tank capacity of a car in liters
+pub fn get_fuel_tank(car_id: &str) -> Option<i32> {
+    get_car(car_id)?.fuel_tank
+}
+
+fn limit(path: &str, min: f64, max: f64) -> ParameterLimit {
+    ParameterLimit {
+        path: path.to_string(),
+        min,
+        max,
+        length: None,
+    }
+}
+
+/// Legal setup parameter ranges (in ACC click indices) for a car, as listed
+/// in the catalog, with the element count of array parameters filled in.
+/// Parameters without a range are not range-checked.
+pub fn get_setup_limits(car_id: &str) -> Option<Vec<ParameterLimit>> {
+    let car = get_car(car_id)?;
+    let mut limits = car.limits.clone();
+    for limit in &mut limits {
+        limit.length = limit.length.or(validation::array_length(&limit.path));
+    }
+
+    // Fuel is stored in liters, so its range follows from the tank size
+    if let Some(fuel_tank) = car.fuel_tank.map(f64::from) {
+        limits.push(limit("basicSetup.strategy.fuel", 0.0, fuel_tank));
+        limits.push(limit(
+            "basicSetup.strategy.pitStrategy.*.fuelToAdd",
+            0.0,
+            fuel_tank,
+        ));
     }
 
-    tracks
+    Some(limits)
 }
 
-/// Helper function to find car by folder name
-pub fn find_car_by_folder(folder_name: &str) -> Option<Car> {
-    get_cars().into_values().find(|car| car.id == folder_name)
+/// Click-to-physical conversions for a car: its own entries followed by the
+/// shared ones it doesn't redefine. Parameters without an entry have no
+/// known conversion.
+pub fn get_parameter_conversions(car_id: &str) -> Option<Vec<ParameterConversion>> {
+    let car = get_car(car_id)?;
+    let mut conversions = car.conversions.clone();
+
+    conversions.extend(
+        catalog()
+            .conversions
+            .iter()
+            .filter(|shared| !car.conversions.iter().any(|own| same_target(own, shared)))
+            .cloned(),
+    );
+
+    Some(conversions)
+}
+
+fn same_target(a: &ParameterConversion, b: &ParameterConversion) -> bool {
+    a.path == b.path && a.index == b.index
 }
 
-/// Helper function to find track by folder name
-pub fn find_track_by_folder(folder_name: &str) -> Option<Track> {
-    get_tracks()
-        .into_values()
-        .find(|track| track.id == folder_name)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn bundled_catalog_loads_with_unique_ids() {
+        let catalog: Catalog = serde_json::from_str(BUNDLED_CATALOG).unwrap();
+        assert_eq!(catalog.cars.len(), 46);
+        assert_eq!(catalog.tracks.len(), 26);
+
+        let car_ids: HashSet<&str> = catalog.cars.iter().map(|car| car.id.as_str()).collect();
+        assert_eq!(car_ids.len(), catalog.cars.len());
+        let track_ids: HashSet<&str> = catalog
+            .tracks
+            .iter()
+            .map(|track| track.id.as_str())
+            .collect();
+        assert_eq!(track_ids.len(), catalog.tracks.len());
+    }
 }
@@ -0,0 +1,93 @@
+use crate::errors::AccResult;
+use crate::models::{ParameterChange, SetupFile};
+use serde_json::Value;
+
+/// Keys that are excluded from diffs because they never describe car behaviour
+const IGNORED_KEYS: &[&str] = &["ACCSMData"];
+
+/// Number of elements in per-wheel arrays such as `tyrePressure` or `camber`
+const CORNER_COUNT: usize = 4;
+
+/// Compare two setups and return every parameter that differs
+pub fn diff_setups(old: &SetupFile, new: &SetupFile) -> AccResult<Vec<ParameterChange>> {
+    let old_value = serde_json::to_value(old)?;
+    let new_value = serde_json::to_value(new)?;
+    Ok(diff_values(&old_value, &new_value))
+}
+
+/// Compare two arbitrary JSON documents and return every leaf that differs
+pub fn diff_values(old: &Value, new: &Value) -> Vec<ParameterChange> {
+    let mut changes = Vec::new();
+    collect_changes("", Some(old), Some(new), &mut changes);
+    changes
+}
+
+fn collect_changes(
+    path: &str,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<ParameterChange>,
+) {
+    match (old, new) {
+        (Some(Value::Object(old_map)), Some(Value::Object(new_map))) => {
+            let mut keys: Vec<&String> = old_map.keys().chain(new_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                if path.is_empty() && IGNORED_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                let child_path = join_path(path, key);
+                collect_changes(&child_path, old_map.get(key), new_map.get(key), changes);
+            }
+        }
+        (Some(Value::Array(old_items)), Some(Value::Array(new_items))) => {
+            let per_corner = old_items.len() == CORNER_COUNT
+                && new_items.len() == CORNER_COUNT
+                && old_items.iter().chain(new_items).all(is_scalar);
+
+            let len = old_items.len().max(new_items.len());
+            for index in 0..len {
+                let old_item = old_items.get(index);
+                let new_item = new_items.get(index);
+
+                if per_corner {
+                    if old_item != new_item {
+                        changes.push(ParameterChange {
+                            path: path.to_string(),
+                            corner: Some(index),
+                            old_value: old_item.cloned(),
+                            new_value: new_item.cloned(),
+                        });
+                    }
+                } else {
+                    let child_path = format!("{}[{}]", path, index);
+                    collect_changes(&child_path, old_item, new_item, changes);
+                }
+            }
+        }
+        (old, new) => {
+            if old != new {
+                changes.push(ParameterChange {
+                    path: path.to_string(),
+                    corner: None,
+                    old_value: old.cloned(),
+                    new_value: new.cloned(),
+                });
+            }
+        }
+    }
+}
+
+fn join_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
@@ -1,8 +1,9 @@
+use crate::models::SetupViolation;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Custom error types for the ACC Setup Manager
-#[derive(Error, Debug, Serialize, Deserialize)]
+#[derive(Error, Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "message")]
 pub enum AccError {
     #[error("Setups folder not found at path: {path}. Please set a valid setups path.")]
@@ -29,18 +30,42 @@ pub enum AccError {
     #[error("Directory creation failed: {path}. Error: {error}")]
     DirectoryCreationFailed { path: String, error: String },
 
+    #[error("A setup already exists at: {path}")]
+    SetupAlreadyExists { path: String },
+
     #[error("File write failed: {path}. Error: {error}")]
     FileWriteFailed { path: String, error: String },
 
+    #[error("Setup archive error: {path}. Error: {error}")]
+    ArchiveFailed { path: String, error: String },
+
+    #[error("Setup library not found: {library_id}")]
+    LibraryNotFound { library_id: String },
+
     #[error("Invalid car ID: {car_id}")]
     InvalidCarId { car_id: String },
 
     #[error("Invalid track ID: {track_id}")]
     InvalidTrackId { track_id: String },
 
+    #[error("Invalid file name: {name}")]
+    InvalidFileName { name: String },
+
     #[error("Setup validation failed: {reason}")]
     SetupValidationFailed { reason: String },
 
+    #[error("Setup has {} invalid parameter(s): {}", .violations.len(), format_violations(.violations))]
+    InvalidSetupParameters { violations: Vec<SetupViolation> },
+
+    #[error("No conversion data for parameter: {path}")]
+    NoConversionData { path: String },
+
+    #[error("Invalid search query: {reason}")]
+    InvalidSearchQuery { reason: String },
+
+    #[error("Invalid fuel strategy: {reason}")]
+    InvalidFuelStrategy { reason: String },
+
     #[error("IO Error: {message}")]
     IoError { message: String },
 
@@ -72,4 +97,12 @@ impl From<serde_json::Error> for AccError {
     }
 }
 
+fn format_violations(violations: &[SetupViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| format!("{} ({})", v.path, v.message))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 pub type AccResult<T> = Result<T, AccError>;
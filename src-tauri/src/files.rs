@@ -0,0 +1,140 @@
+use crate::data;
+use crate::errors::{AccError, AccResult};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Write `contents` to `path` without ever leaving a truncated file behind.
+///
+/// The data goes to a hidden temp file in the same directory, is flushed to
+/// disk and is then renamed over the target. If anything fails the temp file
+/// is removed and the original file is left untouched.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp_path = temp_path_for(path)?;
+
+    let result = write_and_sync(&temp_path, contents).and_then(|()| fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    sync_parent_dir(path);
+    Ok(())
+}
+
+fn write_and_sync(temp_path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(temp_path)?;
+    file.write_all(contents)?;
+    file.flush()?;
+    file.sync_all()
+}
+
+/// Temp files start with a dot so the scanner and watcher ignore them
+fn temp_path_for(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name"))?;
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+
+    Ok(path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name,
+        std::process::id(),
+        nanos
+    )))
+}
+
+/// Persist the rename itself. Directories cannot be opened for syncing on
+/// Windows, so this is best effort only.
+fn sync_parent_dir(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+/// Reject names coming from the frontend that would leave the folder they
+/// are joined onto, e.g. `../x` or `a/b`
+pub fn check_file_name(name: &str) -> AccResult<()> {
+    if name.is_empty()
+        || name == "."
+        || name.contains("..")
+        || name.contains(['/', '\\'])
+        || Path::new(name).is_absolute()
+    {
+        return Err(AccError::InvalidFileName {
+            name: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Folder of a car inside the setups directory. ACC names it after the car
+/// id, but a folder named by one of the car's aliases is used if no folder
+/// with the exact id exists.
+pub fn car_dir(setups_path: &Path, car_id: &str) -> PathBuf {
+    resolve_folder(setups_path, car_id, |name| {
+        data::lookup_car(name).map(|car| car.id.as_str())
+    })
+}
+
+/// Folder of a track inside a car folder, resolved like `car_dir`
+pub fn track_dir(car_dir: &Path, track_id: &str) -> PathBuf {
+    resolve_folder(car_dir, track_id, |name| {
+        data::lookup_track(name).map(|track| track.id.as_str())
+    })
+}
+
+/// Folder holding the setups of a car on a track
+pub fn setup_dir(setups_path: &Path, car_id: &str, track_id: &str) -> PathBuf {
+    track_dir(&car_dir(setups_path, car_id), track_id)
+}
+
+/// Path of a setup file named by the frontend, rejecting names that would
+/// leave the track folder
+pub fn setup_path(
+    setups_path: &Path,
+    car_id: &str,
+    track_id: &str,
+    filename: &str,
+) -> AccResult<PathBuf> {
+    check_file_name(filename)?;
+    Ok(setup_dir(setups_path, car_id, track_id).join(filename))
+}
+
+/// The subfolder of `parent` named `id`, or else the first one whose name
+/// resolves to `id`. Falls back to `parent/id` so new folders use the id.
+fn resolve_folder(
+    parent: &Path,
+    id: &str,
+    resolve: impl Fn(&str) -> Option<&'static str>,
+) -> PathBuf {
+    let exact = parent.join(id);
+    if exact.is_dir() {
+        return exact;
+    }
+
+    let Ok(entries) = fs::read_dir(parent) else {
+        return exact;
+    };
+    let mut candidates: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(&resolve)
+                    == Some(id)
+        })
+        .collect();
+    candidates.sort();
+    candidates.into_iter().next().unwrap_or(exact)
+}
@@ -0,0 +1,220 @@
+use crate::errors::{AccError, AccResult};
+use crate::models::{FuelStint, FuelStrategy, FuelStrategyRequest, RaceLength, Strategy};
+use serde_json::{json, Map, Value};
+
+/// Fuel amounts are kept to this many liters, hiding float noise such as
+/// `56.000000001` before rounding up to whole liters
+const FUEL_PRECISION: f64 = 100.0;
+
+/// Plan start fuel, pit stops and stints for a race. Stints are split as
+/// evenly as possible; earlier stints take the odd laps.
+pub fn calculate(
+    request: &FuelStrategyRequest,
+    strategy: &Strategy,
+    fuel_tank: i32,
+) -> AccResult<FuelStrategy> {
+    let fuel_per_lap = request
+        .fuel_per_lap
+        .or(strategy.fuel_per_lap)
+        .unwrap_or(0.0);
+    if fuel_per_lap <= 0.0 {
+        return Err(invalid("Fuel per lap must be greater than zero"));
+    }
+    if request.safety_margin_laps < 0.0 {
+        return Err(invalid("Safety margin cannot be negative"));
+    }
+
+    let total_laps = race_laps(request)?;
+    let tank = f64::from(fuel_tank);
+    let reserve = request.safety_margin_laps * fuel_per_lap;
+    let formation = if request.formation_lap {
+        fuel_per_lap
+    } else {
+        0.0
+    };
+
+    // Add stops until every stint fits the tank
+    let mut pit_stops = request
+        .pit_stops
+        .or_else(|| {
+            strategy
+                .n_pit_stops
+                .and_then(|stops| u32::try_from(stops).ok())
+        })
+        .unwrap_or(0)
+        .min(total_laps - 1);
+    let stint_laps = loop {
+        if pit_stops >= total_laps {
+            return Err(invalid(&format!(
+                "A {} l tank cannot hold a single lap plus the safety margin",
+                fuel_tank
+            )));
+        }
+        let stint_laps = split_laps(total_laps, pit_stops + 1);
+        let fits = stint_laps.iter().enumerate().all(|(index, laps)| {
+            let extra = if index == 0 { formation } else { 0.0 };
+            round_fuel(f64::from(*laps) * fuel_per_lap + extra + reserve) <= tank
+        });
+        if fits {
+            break stint_laps;
+        }
+        pit_stops += 1;
+    };
+
+    let mut stints: Vec<FuelStint> = Vec::with_capacity(stint_laps.len());
+    let mut fuel_to_add = Vec::with_capacity(stint_laps.len() - 1);
+    for (index, laps) in stint_laps.into_iter().enumerate() {
+        let extra = if index == 0 { formation } else { 0.0 };
+        let fuel_used = round_fuel(f64::from(laps) * fuel_per_lap + extra);
+        let needed = round_fuel(fuel_used + reserve).ceil();
+
+        let start_fuel = match stints.last() {
+            None => needed,
+            Some(previous) => {
+                let refuel = (needed - previous.end_fuel)
+                    .ceil()
+                    .clamp(0.0, (tank - previous.end_fuel).floor());
+                fuel_to_add.push(refuel as i32);
+                round_fuel(previous.end_fuel + refuel)
+            }
+        };
+
+        stints.push(FuelStint {
+            laps,
+            start_fuel,
+            fuel_used,
+            end_fuel: round_fuel(start_fuel - fuel_used),
+        });
+    }
+
+    Ok(FuelStrategy {
+        total_laps,
+        fuel_per_lap,
+        fuel_tank,
+        total_fuel: round_fuel(stints.iter().map(|stint| stint.fuel_used).sum()),
+        start_fuel: stints[0].start_fuel as i32,
+        pit_stops,
+        fuel_to_add,
+        stints,
+    })
+}
+
+/// Write a calculated strategy into the `basicSetup.strategy` block of a
+/// setup. Missing pit stop entries repeat the last one and entries beyond
+/// the planned stops are dropped, so `pitStrategy` matches `nPitStops`.
+pub fn apply(result: &FuelStrategy, content: &mut Value) -> AccResult<()> {
+    let first_stop = new_pit_stop(content);
+    let strategy = content
+        .pointer_mut("/basicSetup/strategy")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| AccError::MissingRequiredField {
+            field: "basicSetup.strategy".to_string(),
+        })?;
+
+    strategy.insert("fuel".to_string(), json!(result.start_fuel));
+    strategy.insert("nPitStops".to_string(), json!(result.pit_stops));
+    strategy.insert("fuelPerLap".to_string(), json!(result.fuel_per_lap));
+
+    let pit_strategy = strategy
+        .entry("pitStrategy")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if !pit_strategy.is_array() {
+        *pit_strategy = Value::Array(Vec::new());
+    }
+    let stops = pit_strategy.as_array_mut().unwrap();
+
+    stops.truncate(result.fuel_to_add.len());
+    for (index, fuel_to_add) in result.fuel_to_add.iter().enumerate() {
+        if index >= stops.len() {
+            let template = stops.last().cloned().unwrap_or_else(|| first_stop.clone());
+            stops.push(template);
+        }
+        if let Some(stop) = stops[index].as_object_mut() {
+            stop.insert("fuelToAdd".to_string(), json!(fuel_to_add));
+        }
+    }
+
+    Ok(())
+}
+
+/// Pit stop entry for a setup that has none yet, fitting the tyres and brake
+/// pads the setup starts with
+fn new_pit_stop(content: &Value) -> Value {
+    let mut stop = Map::new();
+    stop.insert("fuelToAdd".to_string(), json!(0));
+    if let Some(tyres) = content.pointer("/basicSetup/tyres") {
+        stop.insert("tyres".to_string(), tyres.clone());
+    }
+    for key in ["tyreSet", "frontBrakePadCompound", "rearBrakePadCompound"] {
+        if let Some(value) = content.pointer(&format!("/basicSetup/strategy/{}", key)) {
+            stop.insert(key.to_string(), value.clone());
+        }
+    }
+    Value::Object(stop)
+}
+
+/// Number of laps in the race. Timed races end when the lap in progress as
+/// the clock runs out is completed, so a lap that ends exactly as time runs
+/// out is followed by one more.
+fn race_laps(request: &FuelStrategyRequest) -> AccResult<u32> {
+    let laps = match request.race_length {
+        RaceLength::Laps(laps) => laps,
+        RaceLength::Minutes(minutes) => {
+            let lap_time = request
+                .lap_time
+                .filter(|lap_time| *lap_time > 0.0)
+                .ok_or_else(|| invalid("Timed races need a lap time greater than zero"))?;
+            if minutes <= 0.0 {
+                return Err(invalid("Race length must be greater than zero"));
+            }
+            (minutes * 60.0 / lap_time).floor() as u32 + 1
+        }
+    };
+
+    if laps == 0 {
+        return Err(invalid("Race length must be greater than zero"));
+    }
+    Ok(laps)
+}
+
+/// Split a race into `stints` stints of near equal length
+fn split_laps(total_laps: u32, stints: u32) -> Vec<u32> {
+    let base = total_laps / stints;
+    let extra = total_laps % stints;
+    (0..stints)
+        .map(|index| base + u32::from(index < extra))
+        .collect()
+}
+
+fn round_fuel(liters: f64) -> f64 {
+    (liters * FUEL_PRECISION).round() / FUEL_PRECISION
+}
+
+fn invalid(reason: &str) -> AccError {
+    AccError::InvalidFuelStrategy {
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed_race(minutes: f64, lap_time: f64) -> FuelStrategyRequest {
+        FuelStrategyRequest {
+            race_length: RaceLength::Minutes(minutes),
+            lap_time: Some(lap_time),
+            fuel_per_lap: Some(3.0),
+            safety_margin_laps: 0.0,
+            formation_lap: false,
+            pit_stops: None,
+        }
+    }
+
+    #[test]
+    fn timed_races_finish_the_lap_in_progress() {
+        assert_eq!(race_laps(&timed_race(60.0, 121.0)).unwrap(), 30);
+        // The clock runs out just as lap 30 ends, so lap 31 is still driven
+        assert_eq!(race_laps(&timed_race(60.0, 120.0)).unwrap(), 31);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::errors::{AccError, AccResult};
+use crate::files;
+use crate::models::SetupRevision;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Hidden app folder created inside the setups directory
+pub const APP_DIR_NAME: &str = ".accsm";
+
+/// Format used for revision ids, which double as snapshot file names
+const REVISION_ID_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// Folder holding all revisions of a single setup file
+pub fn revisions_dir(
+    setups_path: &Path,
+    car_id: &str,
+    track_id: &str,
+    filename: &str,
+) -> AccResult<PathBuf> {
+    files::check_file_name(filename)?;
+    Ok(setups_path
+        .join(APP_DIR_NAME)
+        .join("history")
+        .join(car_id)
+        .join(track_id)
+        .join(filename))
+}
+
+/// Copy the current content of a setup file into the history store.
+/// Returns `None` if there was no existing file to snapshot.
+pub fn snapshot(
+    setups_path: &Path,
+    car_id: &str,
+    track_id: &str,
+    filename: &str,
+) -> AccResult<Option<SetupRevision>> {
+    let dir_path = revisions_dir(setups_path, car_id, track_id, filename)?;
+    let file_path = files::setup_path(setups_path, car_id, track_id, filename)?;
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(&dir_path).map_err(|e| AccError::DirectoryCreationFailed {
+        path: dir_path.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })?;
+
+    let created_at = Utc::now();
+    let revision_id = unique_timestamp_id(&dir_path, created_at, ".json");
+
+    let revision_path = dir_path.join(format!("{}.json", revision_id));
+    fs::copy(&file_path, &revision_path).map_err(|e| AccError::FileWriteFailed {
+        path: revision_path.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })?;
+
+    let size = fs::metadata(&revision_path).map(|m| m.len()).unwrap_or(0);
+    debug!(
+        "Snapshotted {}/{}/{} as revision {}",
+        car_id, track_id, filename, revision_id
+    );
+
+    Ok(Some(SetupRevision {
+        revision_id,
+        created_at,
+        size,
+    }))
+}
+
+/// List all revisions of a setup file, newest first
+pub fn list_revisions(
+    setups_path: &Path,
+    car_id: &str,
+    track_id: &str,
+    filename: &str,
+) -> AccResult<Vec<SetupRevision>> {
+    let dir_path = revisions_dir(setups_path, car_id, track_id, filename)?;
+    if !dir_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&dir_path).map_err(|e| AccError::IoError {
+        message: format!("Failed to read history directory: {}", e),
+    })?;
+
+    let mut revisions = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| AccError::IoError {
+            message: format!("Failed to read history entry: {}", e),
+        })?;
+
+        let path = entry.path();
+        let Some(revision_id) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(|stem| stem.to_string())
+        else {
+            continue;
+        };
+
+        let metadata = entry.metadata()?;
+        let created_at = parse_revision_id(&revision_id)
+            .or_else(|| metadata.modified().ok().map(DateTime::<Utc>::from))
+            .unwrap_or_else(Utc::now);
+
+        revisions.push(SetupRevision {
+            revision_id,
+            created_at,
+            size: metadata.len(),
+        });
+    }
+
+    revisions.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| b.revision_id.cmp(&a.revision_id))
+    });
+    Ok(revisions)
+}
+
+/// Read the raw content of a stored revision
+pub fn read_revision(
+    setups_path: &Path,
+    car_id: &str,
+    track_id: &str,
+    filename: &str,
+    revision_id: &str,
+) -> AccResult<String> {
+    if parse_revision_id(revision_id).is_none() {
+        return Err(AccError::InvalidFileName {
+            name: revision_id.to_string(),
+        });
+    }
+    let revision_path = revisions_dir(setups_path, car_id, track_id, filename)?
+        .join(format!("{}.json", revision_id));
+
+    if !revision_path.exists() {
+        return Err(AccError::FileNotFound {
+            path: revision_path.to_string_lossy().to_string(),
+        });
+    }
+
+    fs::read_to_string(&revision_path).map_err(|e| AccError::IoError {
+        message: format!("Failed to read setup revision: {}", e),
+    })
+}
+
+/// Move the history of a setup file along with the file itself. Revisions
+/// are merged into any existing history at the destination.
+pub fn move_revisions(
+    setups_path: &Path,
+    from: (&str, &str, &str),
+    to: (&str, &str, &str),
+) -> AccResult<()> {
+    let from_dir = revisions_dir(setups_path, from.0, from.1, from.2)?;
+    if !from_dir.exists() {
+        return Ok(());
+    }
+
+    let to_dir = revisions_dir(setups_path, to.0, to.1, to.2)?;
+    fs::create_dir_all(&to_dir).map_err(|e| AccError::DirectoryCreationFailed {
+        path: to_dir.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })?;
+
+    let entries = fs::read_dir(&from_dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| AccError::IoError {
+            message: format!("Failed to read history directory: {}", e),
+        })?;
+
+    for (moved, entry) in entries.iter().enumerate() {
+        let mut target = to_dir.join(entry.file_name());
+        if target.exists() {
+            // Keep the revision's own time so it stays in place in the history
+            let created_at = Path::new(&entry.file_name())
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(parse_revision_id)
+                .or_else(|| {
+                    let modified = entry.metadata().and_then(|m| m.modified()).ok();
+                    modified.map(DateTime::<Utc>::from)
+                })
+                .unwrap_or_else(Utc::now);
+            target = to_dir.join(format!(
+                "{}.json",
+                unique_timestamp_id(&to_dir, created_at, ".json")
+            ));
+        }
+
+        // Stop before removing the old folder, which still holds the rest
+        fs::rename(entry.path(), &target).map_err(|e| AccError::IoError {
+            message: format!(
+                "Failed to move setup revision after moving {} of {}, the rest are still in {}: {}",
+                moved,
+                entries.len(),
+                from_dir.to_string_lossy(),
+                e
+            ),
+        })?;
+    }
+
+    fs::remove_dir_all(&from_dir).map_err(|e| AccError::IoError {
+        message: format!("Failed to remove old history directory: {}", e),
+    })?;
+
+    debug!(
+        "Moved history {}/{}/{} -> {}/{}/{}",
+        from.0, from.1, from.2, to.0, to.1, to.2
+    );
+    Ok(())
+}
+
+/// Build a timestamp-based id that does not collide with an existing
+/// `<id><suffix>` entry inside `dir_path`
+pub(crate) fn unique_timestamp_id(
+    dir_path: &Path,
+    created_at: DateTime<Utc>,
+    suffix: &str,
+) -> String {
+    let base = created_at.format(REVISION_ID_FORMAT).to_string();
+    let mut id = base.clone();
+    let mut counter = 1;
+    while dir_path.join(format!("{}{}", id, suffix)).exists() {
+        id = format!("{}-{}", base, counter);
+        counter += 1;
+    }
+    id
+}
+
+fn parse_revision_id(revision_id: &str) -> Option<DateTime<Utc>> {
+    // Strip the collision counter suffix if present
+    let (timestamp, counter) = match revision_id.split_once('-') {
+        Some((timestamp, counter)) => (timestamp, Some(counter)),
+        None => (revision_id, None),
+    };
+    if counter.is_some_and(|counter| counter.parse::<u32>().is_err()) {
+        return None;
+    }
+    NaiveDateTime::parse_from_str(timestamp, REVISION_ID_FORMAT)
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moved_revisions_keep_their_time_on_a_name_collision() {
+        let root = std::env::temp_dir().join(format!("accsm_history_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let from = revisions_dir(&root, "bmw_m4_gt3", "monza", "old.json").unwrap();
+        let to = revisions_dir(&root, "bmw_m4_gt3", "monza", "new.json").unwrap();
+        fs::create_dir_all(&from).unwrap();
+        fs::create_dir_all(&to).unwrap();
+
+        let revision_id = "20260102T030405.678Z";
+        fs::write(
+            from.join(format!("{}.json", revision_id)),
+            "{\"moved\": true}",
+        )
+        .unwrap();
+        fs::write(to.join(format!("{}.json", revision_id)), "{}").unwrap();
+
+        move_revisions(
+            &root,
+            ("bmw_m4_gt3", "monza", "old.json"),
+            ("bmw_m4_gt3", "monza", "new.json"),
+        )
+        .unwrap();
+
+        let revisions = list_revisions(&root, "bmw_m4_gt3", "monza", "new.json").unwrap();
+        let ids: Vec<&str> = revisions.iter().map(|r| r.revision_id.as_str()).collect();
+        assert_eq!(ids, ["20260102T030405.678Z-1", revision_id]);
+        assert_eq!(revisions[0].created_at, revisions[1].created_at);
+        assert!(!from.exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
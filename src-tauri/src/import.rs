@@ -0,0 +1,317 @@
+use crate::data;
+use crate::files;
+use crate::models::{AliasMatch, ImportItem, ImportStatus, Track};
+use crate::validation;
+use log::debug;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Minimum length of a track key before it is matched as a part of a name
+const MIN_PHRASE_KEY_LEN: usize = 3;
+
+/// Minimum token length before misspellings are considered
+const MIN_FUZZY_TOKEN_LEN: usize = 5;
+
+/// Maximum edit distance accepted for a misspelled track name
+const MAX_EDIT_DISTANCE: usize = 2;
+
+/// A file to import and the folder holding the path the user picked it
+/// through. Only the part of the path below `root` is used to infer the track.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub root: PathBuf,
+}
+
+/// Expand files and folders into the list of files they contain. Folders are
+/// walked recursively and hidden entries are ignored.
+pub fn collect_files(paths: &[PathBuf]) -> Vec<SourceFile> {
+    let mut files = Vec::new();
+    for path in paths {
+        let root = path.parent().unwrap_or(path);
+        collect_into(path, root, &mut files);
+    }
+    files
+}
+
+fn collect_into(path: &Path, root: &Path, files: &mut Vec<SourceFile>) {
+    let source = || SourceFile {
+        path: path.to_path_buf(),
+        root: root.to_path_buf(),
+    };
+    if path.is_file() {
+        files.push(source());
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(path) else {
+        // Let the planner report the unreadable path
+        files.push(source());
+        return;
+    };
+
+    let mut children: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|child| {
+            !child
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'))
+        })
+        .collect();
+    children.sort();
+
+    for child in children {
+        collect_into(&child, root, files);
+    }
+}
+
+/// Build the import plan for a set of files without touching the library
+pub fn plan_import(
+    setups_path: &Path,
+    files: &[SourceFile],
+    track_override: Option<&'static Track>,
+    overwrite: bool,
+) -> Vec<(ImportItem, Option<serde_json::Value>)> {
+    let mut planned_targets = HashSet::new();
+    files
+        .iter()
+        .map(|file| {
+            plan_file(
+                setups_path,
+                file,
+                track_override,
+                overwrite,
+                &mut planned_targets,
+            )
+        })
+        .collect()
+}
+
+fn plan_file(
+    setups_path: &Path,
+    source: &SourceFile,
+    track_override: Option<&'static Track>,
+    overwrite: bool,
+    planned_targets: &mut HashSet<PathBuf>,
+) -> (ImportItem, Option<serde_json::Value>) {
+    let file = source.path.as_path();
+    let mut item = ImportItem {
+        source_path: file.to_string_lossy().to_string(),
+        status: ImportStatus::Invalid,
+        car: None,
+        track: None,
+        filename: None,
+        reason: None,
+        aliases: Vec::new(),
+        violations: Vec::new(),
+    };
+
+    if !file.is_file() {
+        item.reason = Some("Path is not a readable file".to_string());
+        return (item, None);
+    }
+
+    if file.extension() != Some("json".as_ref()) {
+        item.status = ImportStatus::Skipped;
+        item.reason = Some("Not a JSON file".to_string());
+        return (item, None);
+    }
+
+    let filename = file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("")
+        .to_string();
+    item.filename = Some(filename.clone());
+
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) => {
+            item.reason = Some(format!("Failed to read file: {}", e));
+            return (item, None);
+        }
+    };
+
+    let value: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            item.reason = Some(format!("Invalid JSON: {}", e));
+            return (item, None);
+        }
+    };
+
+    let Some(car_name) = value.get("carName").and_then(|v| v.as_str()) else {
+        item.reason = Some("Missing carName".to_string());
+        return (item, None);
+    };
+
+    let Some((car, car_alias)) = data::resolve_car(car_name) else {
+        item.reason = Some(format!("Unknown car '{}'", car_name));
+        return (item, None);
+    };
+    item.car = Some(car.id.clone());
+    item.aliases.extend(car_alias);
+
+    let inferred = track_override
+        .map(|track| (track, None))
+        .or_else(|| infer_track(file, &source.root));
+    let Some((track, track_alias)) = inferred else {
+        item.reason = Some("Could not determine the track from the path".to_string());
+        return (item, None);
+    };
+    item.track = Some(track.id.clone());
+    item.aliases.extend(track_alias);
+
+    let validation = validation::validate_setup(&car.id, &value);
+    for alias in validation.aliases {
+        validation::push_alias(&mut item.aliases, alias);
+    }
+    let violations = validation.violations;
+    if !violations.is_empty() {
+        item.reason = Some(format!(
+            "Setup has {} invalid parameter(s)",
+            violations.len()
+        ));
+        item.violations = violations;
+        return (item, None);
+    }
+
+    let target = files::setup_dir(setups_path, &car.id, &track.id).join(&filename);
+    if planned_targets.contains(&target) {
+        item.status = ImportStatus::Conflict;
+        item.reason = Some("Another file in this import has the same target".to_string());
+        return (item, None);
+    }
+
+    if target.exists() && !overwrite {
+        item.status = ImportStatus::Conflict;
+        item.reason = Some("A setup with this name already exists".to_string());
+        return (item, None);
+    }
+
+    planned_targets.insert(target);
+    item.status = ImportStatus::Ready;
+    (item, Some(value))
+}
+
+/// Guess the track of a setup from its path below `root`. The parent folder
+/// is tried first, then the file name, then the remaining folders up to
+/// `root`. Also returns the alias if a name matched through the alias table.
+pub fn infer_track(file: &Path, root: &Path) -> Option<(&'static Track, Option<AliasMatch>)> {
+    let folders: Vec<&str> = file
+        .parent()
+        .and_then(|parent| parent.strip_prefix(root).ok())
+        .map(|relative| {
+            relative
+                .components()
+                .filter_map(|component| component.as_os_str().to_str())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut candidates = Vec::new();
+    candidates.extend(folders.last().copied());
+    candidates.extend(file.file_stem().and_then(|n| n.to_str()));
+    candidates.extend(folders.iter().rev().skip(1).copied());
+
+    candidates.into_iter().find_map(|candidate| {
+        if let Some(resolved) = data::resolve_track(candidate) {
+            return Some(resolved);
+        }
+        let track = match_track_name(candidate)?;
+        debug!("Matched '{}' to track {}", candidate, track.id);
+        Some((track, None))
+    })
+}
+
+/// Fuzzy-match a free-form name against the known tracks
+pub fn match_track_name(name: &str) -> Option<&'static Track> {
+    let normalized = normalize(name);
+    if normalized.is_empty() {
+        return None;
+    }
+
+    let keyed: Vec<(Vec<String>, &'static Track)> = data::tracks()
+        .iter()
+        .map(|track| {
+            let mut keys = vec![normalize(&track.id), normalize(&track.pretty_name)];
+            keys.extend(track.aliases.iter().map(|alias| normalize(alias)));
+            (keys, track)
+        })
+        .collect();
+
+    // 1. Exact match on id, pretty name or alias
+    if let Some((_, track)) = keyed.iter().find(|(keys, _)| keys.contains(&normalized)) {
+        return Some(*track);
+    }
+
+    // 2. A run of whole words in the name is a track key, e.g. "spa_24h_race"
+    //    or "Brands Hatch quali"; the longest key wins
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .map(normalize)
+        .filter(|word| !word.is_empty())
+        .collect();
+    let phrases: HashSet<String> = (0..words.len())
+        .flat_map(|start| (start + 1..=words.len()).map(move |end| (start, end)))
+        .map(|(start, end)| words[start..end].concat())
+        .collect();
+
+    if let Some((_, track)) = keyed
+        .iter()
+        .filter_map(|(keys, track)| {
+            keys.iter()
+                .filter(|key| key.len() >= MIN_PHRASE_KEY_LEN && phrases.contains(*key))
+                .map(String::len)
+                .max()
+                .map(|len| (len, *track))
+        })
+        .max_by_key(|(len, _)| *len)
+    {
+        return Some(track);
+    }
+
+    // 3. A word in the name is a near miss of a track id, e.g. "silverstne"
+    keyed
+        .iter()
+        .filter_map(|(keys, track)| {
+            words
+                .iter()
+                .filter(|word| word.len() >= MIN_FUZZY_TOKEN_LEN)
+                .map(|word| edit_distance(word, &keys[0]))
+                .min()
+                .filter(|distance| *distance <= MAX_EDIT_DISTANCE)
+                .map(|distance| (distance, *track))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, track)| track)
+}
+
+/// Lowercase a name and drop everything that is not a letter or digit
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b_chars.len() + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let cost = usize::from(a_char != *b_char);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b_chars.len()]
+}
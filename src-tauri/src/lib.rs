@@ -1,16 +1,28 @@
+pub mod archive;
 pub mod commands;
+pub mod config;
 pub mod data;
+pub mod diff;
 pub mod errors;
+pub mod files;
+pub mod fuel;
+pub mod history;
+pub mod import;
 pub mod models;
+pub mod search;
 pub mod state;
+pub mod trash;
+pub mod units;
+pub mod validation;
 pub mod watcher;
 
 use commands::*;
 use log::{error, info};
 use state::{create_state_manager, AppStateManager};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::{Emitter, Manager};
-use watcher::FileWatcher;
+use watcher::{FileWatcher, SharedWatcher};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -32,49 +44,72 @@ pub fn run() {
             let state_manager = create_state_manager();
             app.manage(Arc::clone(&state_manager));
 
-            // Initial folder structure scan
+            // Initial folder structure scan of every library
             let state_clone: Arc<AppStateManager> = Arc::clone(&state_manager);
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                match state_clone.refresh_folder_structure().await {
-                    Ok(()) => {
-                        info!("Initial folder structure scan completed");
+                for library in state_clone.libraries().await {
+                    match library.refresh_folder_structure().await {
+                        Ok(()) => {
+                            info!("Initial scan of library {} completed", library.id());
 
-                        // Emit initial setups-changed event
-                        match state_clone.get_folder_structure().await {
-                            Ok(structure) => {
-                                if let Err(e) = app_handle.emit("setups-changed", &structure) {
-                                    error!("Failed to emit initial setups-changed event: {}", e);
-                                } else {
-                                    info!("Emitted initial setups-changed event");
+                            // Emit initial setups-changed event
+                            match library.get_folder_structure().await {
+                                Ok(structure) => {
+                                    if let Err(e) = app_handle.emit("setups-changed", &structure) {
+                                        error!(
+                                            "Failed to emit initial setups-changed event: {}",
+                                            e
+                                        );
+                                    } else {
+                                        info!("Emitted initial setups-changed event");
+                                    }
+                                }
+                                Err(e) => {
+                                    error!(
+                                        "Failed to get folder structure for initial event: {}",
+                                        e
+                                    );
                                 }
-                            }
-                            Err(e) => {
-                                error!("Failed to get folder structure for initial event: {}", e);
                             }
                         }
+                        Err(e) => {
+                            error!("Initial scan of library {} failed: {}", library.id(), e);
+                            // This is not a critical error - the user can set the path later
+                        }
                     }
-                    Err(e) => {
-                        error!("Initial folder structure scan failed: {}", e);
-                        // This is not a critical error - the user can set the path later
-                    }
+                }
+
+                if let Err(e) = state_clone.purge_expired_trash().await {
+                    error!("Failed to purge expired trash items: {}", e);
                 }
             });
 
-            // Setup file watcher
+            // Setup a file watcher per library, kept in managed state so they can
+            // follow path changes and libraries being added or removed
+            let watchers: SharedWatcher = Arc::new(tokio::sync::Mutex::new(HashMap::new()));
+            app.manage(Arc::clone(&watchers));
+
             let state_clone: Arc<AppStateManager> = Arc::clone(&state_manager);
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                let setups_path = state_clone.get_setups_path().await;
-                match FileWatcher::new(&setups_path, state_clone, app_handle) {
-                    Ok(_watcher) => {
-                        info!("File watcher started successfully");
-                        // Keep the watcher alive by not dropping it
-                        // In a real application, you might want to store this in the app state
-                        tokio::signal::ctrl_c().await.ok();
-                    }
-                    Err(e) => {
-                        error!("Failed to start file watcher: {}", e);
+                for library in state_clone.libraries().await {
+                    let setups_path = library.get_setups_path().await;
+                    let library_id = library.id().to_string();
+                    match FileWatcher::new(&setups_path, library, app_handle.clone()) {
+                        Ok(watcher) => {
+                            info!(
+                                "File watcher for library {} started successfully",
+                                library_id
+                            );
+                            watchers.lock().await.insert(library_id, watcher);
+                        }
+                        Err(e) => {
+                            error!(
+                                "Failed to start file watcher for library {}: {}",
+                                library_id, e
+                            );
+                        }
                     }
                 }
             });
@@ -93,7 +128,45 @@ pub fn run() {
             get_tracks,
             refresh_folder_structure,
             setup_exists,
-            validate_setup
+            validate_setup,
+            diff_setups,
+            list_setup_history,
+            get_setup_revision,
+            diff_setup_revisions,
+            restore_setup_revision,
+            list_trash,
+            restore_from_trash,
+            empty_trash,
+            get_trash_retention_days,
+            set_trash_retention_days,
+            get_setup_limits,
+            get_parameter_conversions,
+            get_setup_physical_values,
+            physical_value_to_clicks,
+            calculate_fuel_strategy,
+            copy_setup,
+            move_setup,
+            rename_setup,
+            import_setups,
+            export_setups,
+            import_setup_pack,
+            get_watcher_debounce_ms,
+            set_watcher_debounce_ms,
+            get_config,
+            update_config,
+            list_libraries,
+            add_library,
+            remove_library,
+            set_live_library,
+            copy_setup_between_libraries,
+            search_setups,
+            list_tags,
+            rename_tag,
+            merge_tags,
+            delete_tag,
+            find_setup_by_id,
+            update_setup_metadata,
+            get_scan_diagnostics
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,12 +1,44 @@
+use crate::errors::AccError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+
+/// Racing class of a car
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CarClass {
+    Gt3,
+    Gt4,
+    Gt2,
+    Gtc,
+    Tcx,
+}
 
 /// Car metadata structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Car {
     pub id: String,
     pub pretty_name: String,
+    pub class: CarClass,
+    #[serde(default)]
+    pub manufacturer: String,
+    #[serde(default)]
+    pub year: Option<u16>,
+    /// DLC pack the car ships with, `None` for base game content
+    #[serde(default)]
+    pub dlc: Option<String>,
+    /// Fuel tank capacity in liters, `None` if unknown
+    #[serde(default)]
+    pub fuel_tank: Option<i32>,
+    /// Other names the car is known by, e.g. legacy folder names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Legal setup parameter ranges, served by `get_setup_limits`
+    #[serde(default, skip_serializing)]
+    pub limits: Vec<ParameterLimit>,
+    /// Click-to-physical conversions of this car, taking precedence over the
+    /// catalog's shared ones
+    #[serde(default, skip_serializing)]
+    pub conversions: Vec<ParameterConversion>,
 }
 
 /// Track metadata structure
@@ -14,49 +46,309 @@ pub struct Car {
 pub struct Track {
     pub id: String,
     pub pretty_name: String,
+    /// DLC pack the track ships with, `None` for base game content
+    #[serde(default)]
+    pub dlc: Option<String>,
+    /// Other names the track is known by, e.g. legacy folder names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// A name that resolved to a catalog id other than by exact match
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasMatch {
+    /// The name as found, e.g. a folder name or `carName` value
+    pub name: String,
+    /// The catalog id it resolved to
+    pub id: String,
+    /// The alias that matched, `None` if only the case differs from the id
+    pub alias: Option<String>,
 }
 
 /// Custom metadata added to setup files
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AccsmData {
+    /// Stable identity of the setup that survives renames and moves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub last_modified: DateTime<Utc>,
     pub tags: Vec<String>,
     pub setup_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Where the setup came from, e.g. a forum post or setup shop URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// ACC version the setup was made for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<TrackConditions>,
+    /// User rating from 1 to 5
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
 }
 
 impl Default for AccsmData {
     fn default() -> Self {
         Self {
+            id: None,
             last_modified: Utc::now(),
             tags: Vec::new(),
             setup_type: "race".to_string(),
+            author: None,
+            notes: None,
+            source_url: None,
+            game_version: None,
+            conditions: None,
+            rating: None,
         }
     }
 }
 
+/// Weather window a setup is meant for, temperatures in °C
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TrackConditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub air_temp_min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub air_temp_max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_temp_min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_temp_max: Option<f64>,
+}
+
+/// Tyre settings inside `basicSetup.tyres`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tyres {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tyre_set: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tyre_compound: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tyre_pressure: Option<Vec<i32>>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Wheel alignment settings inside `basicSetup.alignment`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alignment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camber: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toe: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub static_camber: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toe_out_linear: Option<Vec<f64>>,
+    #[serde(rename = "casterLF", skip_serializing_if = "Option::is_none")]
+    pub caster_lf: Option<i32>,
+    #[serde(rename = "casterRF", skip_serializing_if = "Option::is_none")]
+    pub caster_rf: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub steer_ratio: Option<i32>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Electronics settings inside `basicSetup.electronics`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Electronics {
+    #[serde(rename = "tC1", skip_serializing_if = "Option::is_none")]
+    pub tc1: Option<i32>,
+    #[serde(rename = "tC2", skip_serializing_if = "Option::is_none")]
+    pub tc2: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abs: Option<i32>,
+    #[serde(rename = "eCUMap", skip_serializing_if = "Option::is_none")]
+    pub ecu_map: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuel_mix: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry_laps: Option<i32>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A single pit stop entry inside `basicSetup.strategy.pitStrategy`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PitStop {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuel_to_add: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tyres: Option<Tyres>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tyre_strategy: Option<i32>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Fuel and pit strategy inside `basicSetup.strategy`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Strategy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_pit_stops: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tyre_set: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub front_brake_pad_compound: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rear_brake_pad_compound: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pit_strategy: Option<Vec<PitStop>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuel_per_lap: Option<f64>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The `basicSetup` block of a setup file. Every field is optional so keys
+/// missing from a file stay missing when it is written back.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BasicSetup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tyres: Option<Tyres>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<Alignment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub electronics: Option<Electronics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<Strategy>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Mechanical grip settings inside `advancedSetup.mechanicalBalance`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MechanicalBalance {
+    #[serde(rename = "aRBFront", skip_serializing_if = "Option::is_none")]
+    pub arb_front: Option<i32>,
+    #[serde(rename = "aRBRear", skip_serializing_if = "Option::is_none")]
+    pub arb_rear: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wheel_rate: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump_stop_rate_up: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump_stop_rate_down: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump_stop_window: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brake_torque: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brake_bias: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ballast_kg: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ballast_pos: Option<i32>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Damper settings inside `advancedSetup.dampers`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dampers {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump_slow: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump_fast: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rebound_slow: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rebound_fast: Option<Vec<i32>>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Aerodynamic settings inside `advancedSetup.aeroBalance`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AeroBalance {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ride_height: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rod_length: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splitter: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rear_wing: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brake_duct: Option<Vec<i32>>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Differential settings inside `advancedSetup.drivetrain`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Drivetrain {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preload: Option<i32>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The `advancedSetup` block of a setup file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdvancedSetup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mechanical_balance: Option<MechanicalBalance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dampers: Option<Dampers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aero_balance: Option<AeroBalance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drivetrain: Option<Drivetrain>,
+    #[serde(flatten)]
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
+}
+
 /// Complete setup file structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SetupFile {
     pub car_name: String,
-    pub basic_setup: serde_json::Value,
-    pub advanced_setup: serde_json::Value,
+    pub basic_setup: BasicSetup,
+    pub advanced_setup: AdvancedSetup,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub track_bop_type: Option<i32>,
-    #[serde(rename = "ACCSMData")]
+    /// Missing in setups written by ACC itself
+    #[serde(rename = "ACCSMData", default)]
     pub accsm_data: AccsmData,
     #[serde(flatten)]
-    pub other_fields: HashMap<String, serde_json::Value>,
+    pub other_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Represents a setup in the folder structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SetupInfo {
+    pub id: Option<String>,
     pub filename: String,
     pub display_name: String,
     pub last_modified: DateTime<Utc>,
     pub tags: Vec<String>,
     pub setup_type: String,
+    pub author: Option<String>,
+    pub rating: Option<u8>,
+    /// Whether the file has ACCSMData. Plain ACC setups are listed with
+    /// default metadata and only get ACCSMData when they are edited.
+    pub managed: bool,
 }
 
 /// Represents a track folder with its setups
@@ -64,6 +356,8 @@ pub struct SetupInfo {
 pub struct TrackFolder {
     pub track_id: String,
     pub track_name: String,
+    /// Set if the folder is named by an alias of the track
+    pub alias: Option<AliasMatch>,
     pub setups: Vec<SetupInfo>,
 }
 
@@ -72,15 +366,28 @@ pub struct TrackFolder {
 pub struct CarFolder {
     pub car_id: String,
     pub car_name: String,
+    /// Set if the folder is named by an alias of the car
+    pub alias: Option<AliasMatch>,
     pub tracks: Vec<TrackFolder>,
 }
 
 /// Complete folder structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FolderStructure {
+    pub library_id: String,
     pub cars: Vec<CarFolder>,
     pub total_setups: usize,
     pub last_scan: DateTime<Utc>,
+    /// Files and folders that were left out of the structure
+    pub diagnostics: Vec<ScanDiagnostic>,
+}
+
+/// A file or folder the scanner could not list, and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanDiagnostic {
+    pub path: String,
+    pub reason: String,
+    pub error: AccError,
 }
 
 /// Request/Response types for Tauri commands
@@ -105,3 +412,348 @@ pub struct DeleteSetupRequest {
     pub track: String,
     pub filename: String,
 }
+
+/// Identifies a single setup file by its car, track and filename
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupRef {
+    pub car: String,
+    pub track: String,
+    pub filename: String,
+}
+
+/// A single changed parameter between two setups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterChange {
+    /// JSON path of the parameter, e.g. `basicSetup.tyres.tyrePressure`
+    pub path: String,
+    /// Corner index (LF, RF, LR, RR) for four-element per-wheel arrays
+    pub corner: Option<usize>,
+    pub old_value: Option<serde_json::Value>,
+    pub new_value: Option<serde_json::Value>,
+}
+
+/// A stored snapshot of a setup file taken before it was overwritten
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupRevision {
+    pub revision_id: String,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// A deleted setup kept in the app-managed trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub car: String,
+    pub track: String,
+    pub filename: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Legal range for a single setup parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterLimit {
+    /// Dot-separated JSON path; `*` matches every element of an array
+    pub path: String,
+    pub min: f64,
+    pub max: f64,
+    /// Required element count when the parameter is an array
+    pub length: Option<usize>,
+}
+
+/// Conversion of a setup parameter from ACC clicks to physical units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterConversion {
+    /// Dot-separated JSON path; `*` matches every element of an array
+    pub path: String,
+    /// Array element the conversion applies to, `None` for every element
+    #[serde(default)]
+    pub index: Option<usize>,
+    pub unit: String,
+    #[serde(flatten)]
+    pub scale: ConversionScale,
+}
+
+/// How the clicks of a parameter map to physical values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConversionScale {
+    /// Evenly spaced clicks: `base + clicks * step`
+    Linear { base: f64, step: f64 },
+    /// Physical value of every click, starting at click 0
+    Table { values: Vec<f64> },
+}
+
+/// A setup parameter with its click value converted to physical units.
+/// Parameters without conversion data only carry their clicks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhysicalValue {
+    /// Concrete JSON path, e.g. `basicSetup.alignment.camber[2]`
+    pub path: String,
+    pub clicks: f64,
+    pub value: Option<f64>,
+    pub unit: Option<String>,
+    /// Physical change to the next click
+    pub step: Option<f64>,
+}
+
+/// A setup file along with the physical value of every parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhysicalSetup {
+    pub setup: SetupFile,
+    pub parameters: Vec<PhysicalValue>,
+}
+
+/// A single problem found while validating a setup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupViolation {
+    pub path: String,
+    pub message: String,
+    pub value: Option<serde_json::Value>,
+}
+
+/// Outcome of validating a setup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SetupValidation {
+    pub violations: Vec<SetupViolation>,
+    /// Car names that were resolved through an alias
+    pub aliases: Vec<AliasMatch>,
+}
+
+/// Result of copying, moving or renaming a setup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupTransfer {
+    pub car: String,
+    pub track: String,
+    pub filename: String,
+    /// Parameters that are out of range for the target car
+    pub warnings: Vec<SetupViolation>,
+}
+
+/// Outcome of a single file in an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    /// Preview only: the file would be imported
+    Ready,
+    Imported,
+    Skipped,
+    Conflict,
+    Invalid,
+}
+
+/// Per-file entry of an import report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportItem {
+    pub source_path: String,
+    pub status: ImportStatus,
+    pub car: Option<String>,
+    pub track: Option<String>,
+    pub filename: Option<String>,
+    pub reason: Option<String>,
+    /// Car and track names that were resolved through an alias
+    pub aliases: Vec<AliasMatch>,
+    /// Parameters that are out of range for the car
+    pub violations: Vec<SetupViolation>,
+}
+
+/// Result of an import, either a preview plan or the committed outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub committed: bool,
+    pub items: Vec<ImportItem>,
+}
+
+/// Kind of change carried by a `setups-delta` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupChangeKind {
+    Upserted,
+    Removed,
+}
+
+/// A single-setup change applied to the cached folder structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupsDelta {
+    pub library_id: String,
+    pub car_id: String,
+    pub track_id: String,
+    pub filename: String,
+    pub kind: SetupChangeKind,
+    /// The new listing entry for upserts
+    pub setup: Option<SetupInfo>,
+    pub total_setups: usize,
+}
+
+/// Health of the setups folder watcher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatcherState {
+    Watching,
+    /// The setups folder does not exist (yet); watching resumes when it appears
+    Unavailable,
+    Error,
+}
+
+/// Payload of the `watcher-status` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherStatus {
+    pub library_id: String,
+    pub path: String,
+    pub state: WatcherState,
+    pub message: Option<String>,
+}
+
+/// Search criteria for `search_setups`. Every field is optional; all given
+/// criteria must match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SetupQuery {
+    /// Free text matched against filename, tags, setup type, car and track
+    pub text: Option<String>,
+    pub car: Option<String>,
+    pub track: Option<String>,
+    pub setup_type: Option<String>,
+    /// Tags that must all be present
+    pub tags: Vec<String>,
+    pub modified_after: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+    /// Parameter predicates such as `basicSetup.electronics.abs >= 3`
+    pub parameters: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+/// A ranked setup search hit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub library_id: String,
+    pub car_id: String,
+    pub car_name: String,
+    pub track_id: String,
+    pub track_name: String,
+    pub setup: SetupInfo,
+    pub score: f64,
+}
+
+/// A tag and the number of setups using it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Outcome of a library-wide tag rename, merge or delete
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagUpdateReport {
+    /// Setup files that were rewritten
+    pub changed: Vec<SetupRef>,
+    /// Setup files that needed a change but could not be rewritten
+    pub failed: Vec<SetupRef>,
+}
+
+/// Editable metadata accepted by `update_setup_metadata`. Omitted fields keep
+/// the stored value and `null` clears it; cleared tags become an empty list
+/// and a cleared setup type falls back to the configured default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SetupMetadata {
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub tags: Option<Option<Vec<String>>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub setup_type: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub author: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub notes: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub source_url: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub game_version: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub conditions: Option<Option<TrackConditions>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "::serde_with::rust::double_option"
+    )]
+    pub rating: Option<Option<u8>>,
+}
+
+/// Length of a race, given either in laps or in minutes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RaceLength {
+    Laps(u32),
+    Minutes(f64),
+}
+
+/// Input of the fuel strategy calculator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuelStrategyRequest {
+    pub race_length: RaceLength,
+    /// Expected lap time in seconds, required for timed races
+    pub lap_time: Option<f64>,
+    /// Liters used per lap, the setup's `fuelPerLap` when omitted
+    pub fuel_per_lap: Option<f64>,
+    /// Reserve kept in the tank at the end of every stint, in laps
+    #[serde(default)]
+    pub safety_margin_laps: f64,
+    #[serde(default)]
+    pub formation_lap: bool,
+    /// Minimum number of stops, the setup's `nPitStops` when omitted. More
+    /// stops are planned if the fuel does not fit the tank.
+    pub pit_stops: Option<u32>,
+}
+
+/// A stint from the start or a pit stop to the next stop or the finish
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuelStint {
+    pub laps: u32,
+    /// Fuel on board when the stint starts
+    pub start_fuel: f64,
+    pub fuel_used: f64,
+    /// Fuel expected to be left when the stint ends
+    pub end_fuel: f64,
+}
+
+/// Result of the fuel strategy calculator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuelStrategy {
+    pub total_laps: u32,
+    pub fuel_per_lap: f64,
+    pub fuel_tank: i32,
+    /// Fuel used over the whole race, including the formation lap
+    pub total_fuel: f64,
+    pub start_fuel: i32,
+    pub pit_stops: u32,
+    /// Fuel to add at each stop, in order
+    pub fuel_to_add: Vec<i32>,
+    pub stints: Vec<FuelStint>,
+}
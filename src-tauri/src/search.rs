@@ -0,0 +1,378 @@
+use crate::errors::{AccError, AccResult};
+use crate::models::{Car, SearchResult, SetupInfo, SetupQuery, SetupRef, Track};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Keys that are not indexed as setup parameters
+const IGNORED_KEYS: &[&str] = &["ACCSMData"];
+
+/// Comparison operators supported in parameter predicates, longest first so
+/// that `>=` is not read as `>`
+const OPERATORS: &[(&str, Comparison)] = &[
+    (">=", Comparison::GreaterOrEqual),
+    ("<=", Comparison::LessOrEqual),
+    ("!=", Comparison::NotEqual),
+    ("==", Comparison::Equal),
+    (">", Comparison::Greater),
+    ("<", Comparison::Less),
+    ("=", Comparison::Equal),
+];
+
+/// Score weights for free-text matches
+const SCORE_FILENAME_EXACT: f64 = 10.0;
+const SCORE_FILENAME: f64 = 5.0;
+const SCORE_TAG_EXACT: f64 = 4.0;
+const SCORE_TAG: f64 = 2.0;
+const SCORE_OTHER: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    fn matches(self, value: f64, target: f64) -> bool {
+        match self {
+            Comparison::Equal => value == target,
+            Comparison::NotEqual => value != target,
+            Comparison::Greater => value > target,
+            Comparison::GreaterOrEqual => value >= target,
+            Comparison::Less => value < target,
+            Comparison::LessOrEqual => value <= target,
+        }
+    }
+}
+
+/// A parsed `path <op> number` condition on a setup parameter
+#[derive(Debug, Clone)]
+struct ParameterPredicate {
+    path: String,
+    comparison: Comparison,
+    value: f64,
+}
+
+impl ParameterPredicate {
+    /// Parse an expression such as `aeroBalance.rearWing < 5`
+    fn parse(expression: &str) -> AccResult<Self> {
+        let invalid = |reason: &str| AccError::InvalidSearchQuery {
+            reason: format!("{} in '{}'", reason, expression),
+        };
+
+        let start = expression
+            .find(['<', '>', '=', '!'])
+            .ok_or_else(|| invalid("Missing comparison operator"))?;
+        let (symbol, comparison) = OPERATORS
+            .iter()
+            .find(|(symbol, _)| expression[start..].starts_with(symbol))
+            .ok_or_else(|| invalid("Unknown comparison operator"))?;
+
+        let path = expression[..start].trim();
+        if path.is_empty() {
+            return Err(invalid("Missing parameter path"));
+        }
+        let value = expression[start + symbol.len()..]
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| invalid("Expected a number"))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            comparison: *comparison,
+            value,
+        })
+    }
+
+    /// A predicate matches if any parameter it addresses satisfies it.
+    /// Paths may omit leading sections, and a path without an index
+    /// addresses every element of an array.
+    fn matches(&self, parameters: &[(String, f64)]) -> bool {
+        parameters
+            .iter()
+            .filter(|(key, _)| path_matches(key, &self.path))
+            .any(|(_, value)| self.comparison.matches(*value, self.value))
+    }
+}
+
+/// Indexed data of a single setup file
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    car_id: String,
+    car_name: String,
+    track_id: String,
+    track_name: String,
+    setup: SetupInfo,
+    /// Numeric leaves keyed by dotted path, e.g. `basicSetup.tyres.tyrePressure[0]`
+    parameters: Vec<(String, f64)>,
+}
+
+/// Key of an indexed setup file: car id, track id and filename
+type EntryKey = (String, String, String);
+
+/// In-memory search index of a library, kept alongside the folder structure
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    entries: HashMap<EntryKey, IndexEntry>,
+    /// Setup ids and the file that owns them
+    ids: HashMap<String, EntryKey>,
+}
+
+impl SearchIndex {
+    /// Add or replace the entry of a setup file
+    pub fn insert(&mut self, car: &Car, track: &Track, setup: SetupInfo, content: &Value) {
+        let mut parameters = Vec::new();
+        collect_parameters("", content, &mut parameters);
+
+        let key = (car.id.clone(), track.id.clone(), setup.filename.clone());
+        self.remove(&key.0, &key.1, &key.2);
+        if let Some(id) = &setup.id {
+            self.ids.insert(id.clone(), key.clone());
+        }
+        self.entries.insert(
+            key,
+            IndexEntry {
+                car_id: car.id.clone(),
+                car_name: car.pretty_name.clone(),
+                track_id: track.id.clone(),
+                track_name: track.pretty_name.clone(),
+                setup,
+                parameters,
+            },
+        );
+    }
+
+    /// Remove the entry of a setup file
+    pub fn remove(&mut self, car_id: &str, track_id: &str, filename: &str) {
+        let key = (
+            car_id.to_string(),
+            track_id.to_string(),
+            filename.to_string(),
+        );
+        let Some(entry) = self.entries.remove(&key) else {
+            return;
+        };
+        if let Some(id) = entry.setup.id {
+            if self.ids.get(&id) == Some(&key) {
+                self.ids.remove(&id);
+            }
+        }
+    }
+
+    /// Make an indexed setup file the owner of an id
+    pub fn set_id_owner(&mut self, id: &str, car_id: &str, track_id: &str, filename: &str) {
+        let key = (
+            car_id.to_string(),
+            track_id.to_string(),
+            filename.to_string(),
+        );
+        if self.entries.contains_key(&key) {
+            self.ids.insert(id.to_string(), key);
+        }
+    }
+
+    /// Find the setup file that owns an id
+    pub fn find_by_id(&self, id: &str) -> Option<SetupRef> {
+        self.ids.get(id).map(|(car, track, filename)| SetupRef {
+            car: car.clone(),
+            track: track.clone(),
+            filename: filename.clone(),
+        })
+    }
+
+    /// Run a query against the index, best matches first
+    pub fn search(&self, library_id: &str, query: &SetupQuery) -> AccResult<Vec<SearchResult>> {
+        let predicates = query
+            .parameters
+            .iter()
+            .map(|expression| ParameterPredicate::parse(expression))
+            .collect::<AccResult<Vec<_>>>()?;
+        let terms: Vec<String> = query
+            .text
+            .as_deref()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+
+        let mut results: Vec<SearchResult> = self
+            .entries
+            .values()
+            .filter(|entry| matches_filters(entry, query, &predicates))
+            .filter_map(|entry| {
+                let score = text_score(entry, &terms)?;
+                Some(SearchResult {
+                    library_id: library_id.to_string(),
+                    car_id: entry.car_id.clone(),
+                    car_name: entry.car_name.clone(),
+                    track_id: entry.track_id.clone(),
+                    track_name: entry.track_name.clone(),
+                    setup: entry.setup.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        // Best score first, most recently modified first on ties
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.setup.last_modified.cmp(&a.setup.last_modified))
+        });
+
+        if let Some(limit) = query.limit {
+            results.truncate(limit);
+        }
+        Ok(results)
+    }
+}
+
+/// Check the structured criteria of a query
+fn matches_filters(
+    entry: &IndexEntry,
+    query: &SetupQuery,
+    predicates: &[ParameterPredicate],
+) -> bool {
+    let setup = &entry.setup;
+
+    if query.car.as_ref().is_some_and(|car| *car != entry.car_id)
+        || query
+            .track
+            .as_ref()
+            .is_some_and(|track| *track != entry.track_id)
+    {
+        return false;
+    }
+
+    if let Some(setup_type) = &query.setup_type {
+        if !setup.setup_type.eq_ignore_ascii_case(setup_type) {
+            return false;
+        }
+    }
+
+    let has_tags = query.tags.iter().all(|wanted| {
+        setup
+            .tags
+            .iter()
+            .any(|tag| tag.eq_ignore_ascii_case(wanted))
+    });
+    if !has_tags {
+        return false;
+    }
+
+    if query
+        .modified_after
+        .is_some_and(|after| setup.last_modified < after)
+        || query
+            .modified_before
+            .is_some_and(|before| setup.last_modified > before)
+    {
+        return false;
+    }
+
+    predicates
+        .iter()
+        .all(|predicate| predicate.matches(&entry.parameters))
+}
+
+/// Score the free-text terms against an entry. Every term has to match
+/// somewhere; `None` means the entry is not a hit.
+fn text_score(entry: &IndexEntry, terms: &[String]) -> Option<f64> {
+    let stem = entry
+        .setup
+        .filename
+        .strip_suffix(".json")
+        .unwrap_or(&entry.setup.filename)
+        .to_lowercase();
+    let display_name = entry.setup.display_name.to_lowercase();
+    let tags: Vec<String> = entry.setup.tags.iter().map(|t| t.to_lowercase()).collect();
+    let others = [
+        entry.setup.setup_type.to_lowercase(),
+        entry.setup.author.as_deref().unwrap_or("").to_lowercase(),
+        entry.car_id.to_lowercase(),
+        entry.car_name.to_lowercase(),
+        entry.track_id.to_lowercase(),
+        entry.track_name.to_lowercase(),
+    ];
+
+    let mut score = 0.0;
+    for term in terms {
+        let mut term_score = 0.0;
+
+        if stem == *term || display_name == *term {
+            term_score += SCORE_FILENAME_EXACT;
+        } else if stem.contains(term.as_str()) || display_name.contains(term.as_str()) {
+            term_score += SCORE_FILENAME;
+        }
+
+        if tags.iter().any(|tag| tag == term) {
+            term_score += SCORE_TAG_EXACT;
+        } else if tags.iter().any(|tag| tag.contains(term.as_str())) {
+            term_score += SCORE_TAG;
+        }
+
+        if others.iter().any(|other| other.contains(term.as_str())) {
+            term_score += SCORE_OTHER;
+        }
+
+        if term_score == 0.0 {
+            return None;
+        }
+        score += term_score;
+    }
+    Some(score)
+}
+
+/// Flatten the numeric leaves of a setup document into dotted paths
+pub fn collect_parameters(path: &str, value: &Value, parameters: &mut Vec<(String, f64)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                if path.is_empty() && IGNORED_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                collect_parameters(&child_path, child, parameters);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_parameters(&format!("{}[{}]", path, index), item, parameters);
+            }
+        }
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                parameters.push((path.to_string(), number));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether an indexed key is addressed by a predicate path. The path matches
+/// whole trailing segments of the key, with or without the array index.
+fn path_matches(key: &str, path: &str) -> bool {
+    let ends_with_segment = |key: &str| {
+        key == path
+            || key
+                .strip_suffix(path)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    };
+
+    if ends_with_segment(key) {
+        return true;
+    }
+    match key.strip_suffix(']').and_then(|k| k.rsplit_once('[')) {
+        Some((array_key, _)) => ends_with_segment(array_key),
+        None => false,
+    }
+}
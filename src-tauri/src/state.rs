@@ -1,28 +1,109 @@
-use crate::data::{find_car_by_folder, find_track_by_folder};
+use crate::archive;
+use crate::config::{self, AppConfig, ConfigStore, LibraryConfig};
+use crate::data::{resolve_car, resolve_track};
 use crate::errors::{AccError, AccResult};
-use crate::models::{AccsmData, CarFolder, FolderStructure, SetupFile, SetupInfo, TrackFolder};
-use chrono::Utc;
+use crate::files;
+use crate::fuel;
+use crate::history;
+use crate::import;
+use crate::models::{
+    AccsmData, AliasMatch, Car, CarFolder, FolderStructure, FuelStrategy, FuelStrategyRequest,
+    ImportItem, ImportReport, ImportStatus, ParameterChange, PhysicalSetup, ScanDiagnostic,
+    SearchResult, SetupChangeKind, SetupFile, SetupInfo, SetupMetadata, SetupQuery, SetupRef,
+    SetupRevision, SetupTransfer, SetupsDelta, TagUpdateReport, TagUsage, Track, TrackFolder,
+    TrashEntry,
+};
+use crate::search::SearchIndex;
+use crate::trash;
+use crate::units;
+use crate::validation;
+use chrono::{DateTime, Utc};
 use log::{debug, info, warn};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
-/// Application state manager that handles setup files and caching
-pub struct AppStateManager {
+/// Default quiet period the watcher waits for before processing a burst of events
+pub const DEFAULT_WATCHER_DEBOUNCE_MS: u64 = 300;
+
+/// How long a path written by the app itself is ignored by the watcher
+const OWN_WRITE_TTL: Duration = Duration::from_secs(5);
+
+/// Size and modification time of a file, `None` if it doesn't exist
+type FileStamp = Option<(u64, SystemTime)>;
+
+/// Outcome of applying a single file change to the cached folder structure
+pub enum CacheUpdate {
+    /// The cache was updated in place
+    Applied(Box<SetupsDelta>),
+    /// The change does not affect the folder structure
+    Ignored,
+    /// The change can't be applied incrementally; a full rescan is needed
+    NeedsRescan,
+}
+
+/// Data collected while scanning a library besides the folder structure
+#[derive(Default)]
+struct ScanState {
+    search_index: SearchIndex,
+    /// Files carrying each setup id, to detect copies that share one
+    id_claims: HashMap<String, Vec<IdClaim>>,
+    diagnostics: Vec<ScanDiagnostic>,
+}
+
+/// A setup file found carrying an id during a scan
+struct IdClaim {
+    path: PathBuf,
+    car: Car,
+    track: Track,
+    filename: String,
+    modified: SystemTime,
+}
+
+impl ScanState {
+    /// Record a file or folder that is left out of the folder structure
+    fn report(&mut self, path: &Path, reason: String, error: AccError) {
+        warn!("{}: {}", reason, error);
+        self.diagnostics.push(ScanDiagnostic {
+            path: path.to_string_lossy().to_string(),
+            reason,
+            error,
+        });
+    }
+}
+
+/// A single setup library: one setups folder with its own cached structure
+pub struct Library {
+    id: String,
     setups_path: RwLock<PathBuf>,
     folder_structure: RwLock<Option<FolderStructure>>,
+    search_index: RwLock<SearchIndex>,
+    config: Arc<ConfigStore>,
+    own_writes: Mutex<HashMap<PathBuf, (Instant, FileStamp)>>,
 }
 
-impl AppStateManager {
-    /// Create a new state manager with the given setups path
-    pub fn new(setups_path: PathBuf) -> Self {
+impl Library {
+    /// Create a library rooted at the given setups path
+    pub fn new(id: String, setups_path: PathBuf, config: Arc<ConfigStore>) -> Self {
         Self {
+            id,
             setups_path: RwLock::new(setups_path),
             folder_structure: RwLock::new(None),
+            search_index: RwLock::new(SearchIndex::default()),
+            config,
+            own_writes: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Get the id of this library
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
     /// Get the current setups path
     pub async fn get_setups_path(&self) -> PathBuf {
         self.setups_path.read().await.clone()
@@ -36,9 +117,18 @@ impl AppStateManager {
             });
         }
 
-        *self.setups_path.write().await = new_path;
+        *self.setups_path.write().await = new_path.clone();
         self.refresh_folder_structure().await?;
         info!("Updated setups path and refreshed structure");
+
+        let library_id = self.id.clone();
+        self.config
+            .update(|config| {
+                if let Some(library) = config.libraries.iter_mut().find(|l| l.id == library_id) {
+                    library.path = new_path;
+                }
+            })
+            .await?;
         Ok(())
     }
 
@@ -56,6 +146,22 @@ impl AppStateManager {
         }
     }
 
+    /// Search the setups of this library, scanning first if necessary
+    pub async fn search_setups(&self, query: &SetupQuery) -> AccResult<Vec<SearchResult>> {
+        if self.folder_structure.read().await.is_none() {
+            self.refresh_folder_structure().await?;
+        }
+        self.search_index.read().await.search(&self.id, query)
+    }
+
+    /// Find a setup by its id, scanning first if necessary
+    pub async fn find_setup_by_id(&self, id: &str) -> AccResult<Option<SetupRef>> {
+        if self.folder_structure.read().await.is_none() {
+            self.refresh_folder_structure().await?;
+        }
+        Ok(self.search_index.read().await.find_by_id(id))
+    }
+
     /// Force refresh the folder structure from disk
     pub async fn refresh_folder_structure(&self) -> AccResult<()> {
         let setups_path = self.get_setups_path().await;
@@ -67,14 +173,287 @@ impl AppStateManager {
             });
         }
 
-        let structure = self.scan_folder_structure(&setups_path).await?;
-        *self.folder_structure.write().await = Some(structure);
+        let mut scan = ScanState::default();
+        let mut structure = self.scan_folder_structure(&setups_path, &mut scan).await?;
+        self.resolve_shared_ids(&mut structure, &mut scan).await;
+        let mut cached = self.folder_structure.write().await;
+        *self.search_index.write().await = scan.search_index;
+        *cached = Some(structure);
         info!("Refreshed folder structure from disk");
         Ok(())
     }
 
+    /// Update the cached folder structure for a single changed setup file
+    /// at `<setups>/<car>/<track>/<file>.json` without rescanning the library
+    pub async fn apply_setup_change(&self, file_path: &Path) -> AccResult<CacheUpdate> {
+        let setups_path = self.get_setups_path().await;
+
+        let Ok(relative_path) = file_path.strip_prefix(&setups_path) else {
+            return Ok(CacheUpdate::Ignored);
+        };
+        let components: Vec<String> = relative_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let [car_folder, track_folder, filename] = components.as_slice() else {
+            return Ok(CacheUpdate::NeedsRescan);
+        };
+
+        if filename.starts_with('.') || !filename.ends_with(".json") {
+            return Ok(CacheUpdate::Ignored);
+        }
+        let (Some((car, _)), Some((track, _))) =
+            (resolve_car(car_folder), resolve_track(track_folder))
+        else {
+            // Unknown folders are skipped by the full scan as well
+            return Ok(CacheUpdate::Ignored);
+        };
+        if file_path.parent() != Some(&files::setup_dir(&setups_path, &car.id, &track.id)) {
+            // Alias folders shadowed by the real folder are skipped as well
+            return Ok(CacheUpdate::Ignored);
+        }
+
+        // Read the file before taking the write lock
+        let mut diagnostic = None;
+        let mut setup = if file_path.is_file() {
+            match self.get_setup_info(file_path, filename).await {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Error reading setup file {}: {}", filename, e);
+                    diagnostic = Some(ScanDiagnostic {
+                        path: file_path.to_string_lossy().to_string(),
+                        reason: format!("Could not read setup file '{}'", filename),
+                        error: e,
+                    });
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // A new or copied file may carry an id that another setup already owns
+        if let Some((info, content)) = setup.as_mut() {
+            let owner = match &info.id {
+                Some(id) => self.search_index.read().await.find_by_id(id),
+                None => None,
+            };
+            let duplicate = owner.is_some_and(|owner| {
+                (owner.car != car.id || owner.track != track.id || owner.filename != *filename)
+                    && setups_path
+                        .join(&owner.car)
+                        .join(&owner.track)
+                        .join(&owner.filename)
+                        .is_file()
+            });
+            if (info.id.is_none() && info.managed) || duplicate {
+                if let Err(e) = self.assign_setup_id(file_path, info, content) {
+                    warn!("Failed to assign a new id to {}: {}", filename, e);
+                }
+            }
+        }
+
+        let mut guard = self.folder_structure.write().await;
+        let Some(structure) = guard.as_mut() else {
+            return Ok(CacheUpdate::NeedsRescan);
+        };
+
+        // Keep the diagnostics of this file in line with its new state
+        let path_string = file_path.to_string_lossy();
+        structure
+            .diagnostics
+            .retain(|diagnostic| diagnostic.path != path_string);
+        structure.diagnostics.extend(diagnostic);
+
+        let mut search_index = self.search_index.write().await;
+        let (kind, setup) = match setup {
+            Some((info, content)) => {
+                upsert_setup_info(structure, car, track, info.clone());
+                search_index.insert(car, track, info.clone(), &content);
+                (SetupChangeKind::Upserted, Some(info))
+            }
+            None => {
+                if !remove_setup_info(structure, &car.id, &track.id, filename) {
+                    return Ok(CacheUpdate::Ignored);
+                }
+                search_index.remove(&car.id, &track.id, filename);
+                (SetupChangeKind::Removed, None)
+            }
+        };
+
+        structure.total_setups = structure
+            .cars
+            .iter()
+            .flat_map(|c| &c.tracks)
+            .map(|t| t.setups.len())
+            .sum();
+        structure.last_scan = Utc::now();
+
+        debug!(
+            "Applied incremental change to {}/{}/{}",
+            car.id, track.id, filename
+        );
+
+        Ok(CacheUpdate::Applied(Box::new(SetupsDelta {
+            library_id: self.id.clone(),
+            car_id: car.id.clone(),
+            track_id: track.id.clone(),
+            filename: filename.clone(),
+            kind,
+            setup,
+            total_setups: structure.total_setups,
+        })))
+    }
+
+    /// Remember that the app itself just changed a setup file, and patch the
+    /// cache right away so the watcher doesn't need to rescan for it
+    async fn record_own_write(&self, file_path: &Path) {
+        self.mark_own_write(file_path);
+
+        if let Err(e) = self.apply_setup_change(file_path).await {
+            warn!("Failed to update cached structure after write: {}", e);
+        }
+    }
+
+    /// Remember that the app itself just changed a setup file, along with
+    /// the state it left the file in
+    fn mark_own_write(&self, file_path: &Path) {
+        if let Ok(mut own_writes) = self.own_writes.lock() {
+            own_writes.insert(
+                file_path.to_path_buf(),
+                (Instant::now(), file_stamp(file_path)),
+            );
+        }
+    }
+
+    /// Give a setup file a fresh id and write it back, leaving everything
+    /// else in the file as it is. Returns the new id.
+    fn assign_setup_id(
+        &self,
+        file_path: &Path,
+        info: &mut SetupInfo,
+        content: &mut serde_json::Value,
+    ) -> AccResult<String> {
+        let id = Uuid::new_v4().to_string();
+        let Some(accsm_data) = content
+            .get_mut("ACCSMData")
+            .and_then(|data| data.as_object_mut())
+        else {
+            return Err(AccError::MissingRequiredField {
+                field: "ACCSMData".to_string(),
+            });
+        };
+        accsm_data.insert("id".to_string(), serde_json::Value::String(id.clone()));
+
+        let json_string = serde_json::to_string_pretty(content)?;
+        files::write_atomic(file_path, json_string.as_bytes()).map_err(|e| {
+            AccError::FileWriteFailed {
+                path: file_path.to_string_lossy().to_string(),
+                error: e.to_string(),
+            }
+        })?;
+        self.mark_own_write(file_path);
+
+        debug!("Assigned id {} to {:?}", id, file_path);
+        info.id = Some(id.clone());
+        Ok(id)
+    }
+
+    /// Give a fresh id to every file that copies another file's id. The id
+    /// stays with the file the previous index linked it to, or else with the
+    /// file modified first.
+    async fn resolve_shared_ids(&self, structure: &mut FolderStructure, scan: &mut ScanState) {
+        let previous_index = self.search_index.read().await;
+        let claims = std::mem::take(&mut scan.id_claims);
+
+        for (id, mut claims) in claims {
+            if claims.len() < 2 {
+                continue;
+            }
+            let owner = previous_index.find_by_id(&id).and_then(|owner| {
+                claims.iter().position(|claim| {
+                    claim.car.id == owner.car
+                        && claim.track.id == owner.track
+                        && claim.filename == owner.filename
+                })
+            });
+            let owner = owner.unwrap_or_else(|| {
+                (0..claims.len())
+                    .min_by_key(|&index| claims[index].modified)
+                    .unwrap_or(0)
+            });
+            let owner = claims.swap_remove(owner);
+
+            for claim in claims {
+                let reissued = async {
+                    let (mut info, mut content) =
+                        self.get_setup_info(&claim.path, &claim.filename).await?;
+                    self.assign_setup_id(&claim.path, &mut info, &mut content)?;
+                    AccResult::Ok((info, content))
+                };
+                let (info, content) = match reissued.await {
+                    Ok(reissued) => reissued,
+                    Err(e) => {
+                        warn!("Failed to assign a new id to {}: {}", claim.filename, e);
+                        continue;
+                    }
+                };
+                info!(
+                    "{} shares id {} with another setup, issued {}",
+                    claim.filename,
+                    id,
+                    info.id.as_deref().unwrap_or_default()
+                );
+
+                let cached = structure
+                    .cars
+                    .iter_mut()
+                    .filter(|car| car.car_id == claim.car.id)
+                    .flat_map(|car| car.tracks.iter_mut())
+                    .filter(|track| track.track_id == claim.track.id)
+                    .flat_map(|track| track.setups.iter_mut())
+                    .find(|setup| setup.filename == claim.filename);
+                if let Some(cached) = cached {
+                    *cached = info.clone();
+                }
+                scan.search_index
+                    .insert(&claim.car, &claim.track, info, &content);
+            }
+            scan.search_index
+                .set_id_owner(&id, &owner.car.id, &owner.track.id, &owner.filename);
+        }
+    }
+
+    /// Whether a path is still as the app itself recently left it. Such events
+    /// are already reflected in the cache and can be skipped by the watcher.
+    /// Once the file changed again the mark is dropped, so later changes by
+    /// other programs are picked up.
+    pub fn is_own_write(&self, file_path: &Path) -> bool {
+        let Ok(mut own_writes) = self.own_writes.lock() else {
+            return false;
+        };
+        own_writes.retain(|_, (written_at, _)| written_at.elapsed() < OWN_WRITE_TTL);
+        let Some((_, stamp)) = own_writes.get(file_path) else {
+            return false;
+        };
+        if *stamp == file_stamp(file_path) {
+            return true;
+        }
+        own_writes.remove(file_path);
+        false
+    }
+
+    /// Get the quiet period the watcher waits for before processing events
+    pub async fn get_watcher_debounce(&self) -> Duration {
+        Duration::from_millis(self.config.read().await.watcher_debounce_ms)
+    }
+
     /// Scan the setups directory and build the folder structure
-    async fn scan_folder_structure(&self, setups_path: &Path) -> AccResult<FolderStructure> {
+    async fn scan_folder_structure(
+        &self,
+        setups_path: &Path,
+        scan: &mut ScanState,
+    ) -> AccResult<FolderStructure> {
         let mut cars = Vec::new();
         let mut total_setups = 0;
 
@@ -104,22 +483,49 @@ impl AppStateManager {
                 continue;
             }
 
-            if let Some(car) = find_car_by_folder(&folder_name) {
-                match self.scan_car_folder(&path, &car.id).await {
-                    Ok(car_folder) => {
-                        total_setups += car_folder
-                            .tracks
-                            .iter()
-                            .map(|t| t.setups.len())
-                            .sum::<usize>();
-                        cars.push(car_folder);
-                    }
-                    Err(e) => {
-                        warn!("Error scanning car folder {}: {}", folder_name, e);
-                    }
+            let Some((car, alias)) = resolve_car(&folder_name) else {
+                scan.report(
+                    &path,
+                    format!("Unknown car folder '{}'", folder_name),
+                    AccError::InvalidCarId {
+                        car_id: folder_name,
+                    },
+                );
+                continue;
+            };
+            if files::car_dir(setups_path, &car.id) != path {
+                scan.report(
+                    &path,
+                    format!(
+                        "Folder '{}' is an alias of {}, whose folder is used instead",
+                        folder_name, car.pretty_name
+                    ),
+                    AccError::InvalidCarId {
+                        car_id: folder_name,
+                    },
+                );
+                continue;
+            }
+            if let Some(alias) = &alias {
+                info!("Car folder '{}' resolved to {}", alias.name, alias.id);
+            }
+
+            match self.scan_car_folder(&path, car, alias, scan).await {
+                Ok(car_folder) => {
+                    total_setups += car_folder
+                        .tracks
+                        .iter()
+                        .map(|t| t.setups.len())
+                        .sum::<usize>();
+                    cars.push(car_folder);
+                }
+                Err(e) => {
+                    scan.report(
+                        &path,
+                        format!("Could not scan car folder '{}'", folder_name),
+                        e,
+                    );
                 }
-            } else {
-                debug!("Unknown car folder: {}", folder_name);
             }
         }
 
@@ -127,22 +533,22 @@ impl AppStateManager {
         cars.sort_by(|a, b| a.car_name.cmp(&b.car_name));
 
         Ok(FolderStructure {
+            library_id: self.id.clone(),
             cars,
             total_setups,
             last_scan: Utc::now(),
+            diagnostics: std::mem::take(&mut scan.diagnostics),
         })
     }
 
     /// Scan a car folder for track directories
-    async fn scan_car_folder(&self, car_path: &Path, car_id: &str) -> AccResult<CarFolder> {
-        let cars_data = crate::data::get_cars();
-        let car = cars_data
-            .get(car_id)
-            .cloned()
-            .ok_or_else(|| AccError::InvalidCarId {
-                car_id: car_id.to_string(),
-            })?;
-
+    async fn scan_car_folder(
+        &self,
+        car_path: &Path,
+        car: &Car,
+        alias: Option<AliasMatch>,
+        scan: &mut ScanState,
+    ) -> AccResult<CarFolder> {
         let mut tracks = Vec::new();
 
         let entries = fs::read_dir(car_path).map_err(|e| AccError::IoError {
@@ -170,22 +576,49 @@ impl AppStateManager {
                 continue;
             }
 
-            if let Some(track) = find_track_by_folder(&folder_name) {
-                match self.scan_track_folder(&path, &track.id).await {
-                    Ok(track_folder) => {
-                        if !track_folder.setups.is_empty() {
-                            tracks.push(track_folder);
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Error scanning track folder {}: {}", folder_name, e);
+            let Some((track, alias)) = resolve_track(&folder_name) else {
+                scan.report(
+                    &path,
+                    format!(
+                        "Unknown track folder '{}' in {}",
+                        folder_name, car.pretty_name
+                    ),
+                    AccError::InvalidTrackId {
+                        track_id: folder_name,
+                    },
+                );
+                continue;
+            };
+            if files::track_dir(car_path, &track.id) != path {
+                scan.report(
+                    &path,
+                    format!(
+                        "Folder '{}' in {} is an alias of {}, whose folder is used instead",
+                        folder_name, car.pretty_name, track.pretty_name
+                    ),
+                    AccError::InvalidTrackId {
+                        track_id: folder_name,
+                    },
+                );
+                continue;
+            }
+            if let Some(alias) = &alias {
+                info!("Track folder '{}' resolved to {}", alias.name, alias.id);
+            }
+
+            match self.scan_track_folder(&path, car, track, alias, scan).await {
+                Ok(track_folder) => {
+                    if !track_folder.setups.is_empty() {
+                        tracks.push(track_folder);
                     }
                 }
-            } else {
-                debug!(
-                    "Unknown track folder in {}: {}",
-                    car.pretty_name, folder_name
-                );
+                Err(e) => {
+                    scan.report(
+                        &path,
+                        format!("Could not scan track folder '{}'", folder_name),
+                        e,
+                    );
+                }
             }
         }
 
@@ -195,20 +628,20 @@ impl AppStateManager {
         Ok(CarFolder {
             car_id: car.id.clone(),
             car_name: car.pretty_name.clone(),
+            alias,
             tracks,
         })
     }
 
     /// Scan a track folder for setup files
-    async fn scan_track_folder(&self, track_path: &Path, track_id: &str) -> AccResult<TrackFolder> {
-        let tracks_data = crate::data::get_tracks();
-        let track = tracks_data
-            .get(track_id)
-            .cloned()
-            .ok_or_else(|| AccError::InvalidTrackId {
-                track_id: track_id.to_string(),
-            })?;
-
+    async fn scan_track_folder(
+        &self,
+        track_path: &Path,
+        car: &Car,
+        track: &Track,
+        alias: Option<AliasMatch>,
+        scan: &mut ScanState,
+    ) -> AccResult<TrackFolder> {
         let mut setups = Vec::new();
 
         let entries = fs::read_dir(track_path).map_err(|e| AccError::IoError {
@@ -221,7 +654,7 @@ impl AppStateManager {
             })?;
 
             let path = entry.path();
-            if !path.is_file() || path.extension().map_or(true, |ext| ext != "json") {
+            if !path.is_file() || path.extension() != Some("json".as_ref()) {
                 continue;
             }
 
@@ -237,9 +670,43 @@ impl AppStateManager {
             }
 
             match self.get_setup_info(&path, &filename).await {
-                Ok(setup_info) => setups.push(setup_info),
+                Ok((mut setup_info, mut content)) => {
+                    // Issue an id to new managed files. Copies sharing an id
+                    // are sorted out once the whole library is scanned.
+                    match &setup_info.id {
+                        Some(id) => {
+                            let modified = entry
+                                .metadata()
+                                .and_then(|metadata| metadata.modified())
+                                .unwrap_or(SystemTime::UNIX_EPOCH);
+                            scan.id_claims.entry(id.clone()).or_default().push(IdClaim {
+                                path: path.clone(),
+                                car: car.clone(),
+                                track: track.clone(),
+                                filename: filename.clone(),
+                                modified,
+                            });
+                        }
+                        None if setup_info.managed => {
+                            if let Err(e) =
+                                self.assign_setup_id(&path, &mut setup_info, &mut content)
+                            {
+                                warn!("Failed to assign a new id to {}: {}", filename, e);
+                            }
+                        }
+                        None => {}
+                    }
+
+                    scan.search_index
+                        .insert(car, track, setup_info.clone(), &content);
+                    setups.push(setup_info);
+                }
                 Err(e) => {
-                    warn!("Error reading setup file {}: {}", filename, e);
+                    scan.report(
+                        &path,
+                        format!("Could not read setup file '{}'", filename),
+                        e,
+                    );
                 }
             }
         }
@@ -250,55 +717,75 @@ impl AppStateManager {
         Ok(TrackFolder {
             track_id: track.id.clone(),
             track_name: track.pretty_name.clone(),
+            alias,
             setups,
         })
     }
 
-    /// Get setup info from a setup file
-    async fn get_setup_info(&self, file_path: &Path, filename: &str) -> AccResult<SetupInfo> {
-        let content = fs::read_to_string(file_path).map_err(|e| AccError::IoError {
-            message: format!("Failed to read setup file: {}", e),
-        })?;
-
-        let setup: SetupFile =
-            serde_json::from_str(&content).map_err(|e| AccError::InvalidSetupJson {
-                file_path: file_path.to_string_lossy().to_string(),
-                error: e.to_string(),
-            })?;
+    /// Get setup info from a setup file, along with its parsed content
+    async fn get_setup_info(
+        &self,
+        file_path: &Path,
+        filename: &str,
+    ) -> AccResult<(SetupInfo, serde_json::Value)> {
+        let (setup, content) = self.load_setup_file(file_path).await?;
 
         let display_name = filename
             .strip_suffix(".json")
             .unwrap_or(filename)
             .replace('_', " ");
 
-        Ok(SetupInfo {
+        let info = SetupInfo {
+            id: setup.accsm_data.id,
             filename: filename.to_string(),
             display_name,
             last_modified: setup.accsm_data.last_modified,
             tags: setup.accsm_data.tags,
             setup_type: setup.accsm_data.setup_type,
-        })
+            author: setup.accsm_data.author,
+            rating: setup.accsm_data.rating,
+            managed: content.get("ACCSMData").is_some(),
+        };
+        Ok((info, content))
+    }
+
+    /// Read and parse a setup file. Plain ACC setups without ACCSMData get
+    /// default metadata based on the file's modification time; nothing is
+    /// written to the file.
+    async fn load_setup_file(&self, file_path: &Path) -> AccResult<(SetupFile, serde_json::Value)> {
+        let raw = fs::read_to_string(file_path).map_err(|e| AccError::IoError {
+            message: format!("Failed to read setup file: {}", e),
+        })?;
+
+        let invalid_json = |e: serde_json::Error| AccError::InvalidSetupJson {
+            file_path: file_path.to_string_lossy().to_string(),
+            error: e.to_string(),
+        };
+        let content: serde_json::Value = serde_json::from_str(&raw).map_err(invalid_json)?;
+        let mut setup: SetupFile = serde_json::from_value(content.clone()).map_err(invalid_json)?;
+
+        // Plain game files carry no tags until the user edits them
+        if content.get("ACCSMData").is_none() {
+            let last_modified = fs::metadata(file_path)
+                .and_then(|metadata| metadata.modified())
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_else(|_| Utc::now());
+            let config = self.config.read().await;
+            setup.accsm_data = AccsmData {
+                last_modified,
+                setup_type: config.default_setup_type.clone(),
+                ..AccsmData::default()
+            };
+        }
+        Ok((setup, content))
     }
 
     /// Read a complete setup file
     pub async fn read_setup(&self, car: &str, track: &str, filename: &str) -> AccResult<SetupFile> {
         let setups_path = self.get_setups_path().await;
-        let cars_data = crate::data::get_cars();
-        let tracks_data = crate::data::get_tracks();
-
-        let car_data = cars_data.get(car).ok_or_else(|| AccError::InvalidCarId {
-            car_id: car.to_string(),
-        })?;
-        let track_data = tracks_data
-            .get(track)
-            .ok_or_else(|| AccError::InvalidTrackId {
-                track_id: track.to_string(),
-            })?;
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
 
-        let file_path = setups_path
-            .join(&car_data.id)
-            .join(&track_data.id)
-            .join(filename);
+        let file_path = files::setup_path(&setups_path, &car_data.id, &track_data.id, filename)?;
 
         if !file_path.exists() {
             return Err(AccError::FileNotFound {
@@ -306,18 +793,11 @@ impl AppStateManager {
             });
         }
 
-        let content = fs::read_to_string(&file_path).map_err(|e| AccError::IoError {
-            message: format!("Failed to read setup file: {}", e),
-        })?;
-
-        let setup: SetupFile =
-            serde_json::from_str(&content).map_err(|e| AccError::InvalidSetupJson {
-                file_path: file_path.to_string_lossy().to_string(),
-                error: e.to_string(),
-            })?;
+        let (setup, _) = self.load_setup_file(&file_path).await?;
 
-        // Validate car name matches
-        if setup.car_name != car_data.id {
+        // Validate car name matches, accepting aliases of the car
+        let json_car = crate::data::lookup_car(&setup.car_name).map(|car| car.id.as_str());
+        if json_car != Some(car_data.id.as_str()) {
             return Err(AccError::CarNameMismatch {
                 json_car: setup.car_name,
                 folder_car: car_data.id.clone(),
@@ -327,6 +807,56 @@ impl AppStateManager {
         Ok(setup)
     }
 
+    /// Read a setup file along with the physical value of every parameter
+    pub async fn read_setup_physical_values(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+    ) -> AccResult<PhysicalSetup> {
+        let setup = self.read_setup(car, track, filename).await?;
+        let content = serde_json::to_value(&setup)?;
+        let parameters = units::physical_values(car, &content)?;
+        Ok(PhysicalSetup { setup, parameters })
+    }
+
+    /// Calculate the fuel strategy of a setup, optionally writing the result
+    /// into its strategy block
+    pub async fn calculate_fuel_strategy(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+        request: &FuelStrategyRequest,
+        apply: bool,
+    ) -> AccResult<FuelStrategy> {
+        let setups_path = self.get_setups_path().await;
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
+        let file_path = files::setup_path(&setups_path, &car_data.id, &track_data.id, filename)?;
+
+        if !file_path.exists() {
+            return Err(AccError::FileNotFound {
+                path: file_path.to_string_lossy().to_string(),
+            });
+        }
+
+        let (setup, mut content) = self.load_setup_file(&file_path).await?;
+        let fuel_tank = crate::data::get_fuel_tank(&car_data.id).ok_or_else(|| {
+            AccError::InvalidFuelStrategy {
+                reason: format!("No fuel tank capacity is known for {}", car_data.id),
+            }
+        })?;
+        let current = setup.basic_setup.strategy.unwrap_or_default();
+        let strategy = fuel::calculate(request, &current, fuel_tank)?;
+
+        if apply {
+            fuel::apply(&strategy, &mut content)?;
+            self.save_setup(&car_data.id, &track_data.id, filename, content)
+                .await?;
+        }
+        Ok(strategy)
+    }
+
     /// Save a setup file
     pub async fn save_setup(
         &self,
@@ -335,18 +865,9 @@ impl AppStateManager {
         filename: &str,
         mut content: serde_json::Value,
     ) -> AccResult<()> {
+        files::check_file_name(filename)?;
         let setups_path = self.get_setups_path().await;
-        let cars_data = crate::data::get_cars();
-        let tracks_data = crate::data::get_tracks();
-
-        let car_data = cars_data.get(car).ok_or_else(|| AccError::InvalidCarId {
-            car_id: car.to_string(),
-        })?;
-        let track_data = tracks_data
-            .get(track)
-            .ok_or_else(|| AccError::InvalidTrackId {
-                track_id: track.to_string(),
-            })?;
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
 
         // Ensure the content has the required structure
         if !content.is_object() {
@@ -363,26 +884,54 @@ impl AppStateManager {
             serde_json::Value::String(car_data.id.clone()),
         );
 
-        // Add or update ACCSM metadata
+        // Add or update ACCSM metadata, falling back to the configured defaults
+        let (default_tags, default_setup_type) = {
+            let config = self.config.read().await;
+            (
+                config.default_tags.clone(),
+                config.default_setup_type.clone(),
+            )
+        };
+        let existing_data = obj.get("ACCSMData");
         let accsm_data = AccsmData {
+            id: Some(
+                existing_data
+                    .and_then(|data| data.get("id"))
+                    .and_then(|id| id.as_str())
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            ),
             last_modified: Utc::now(),
-            tags: obj
-                .get("ACCSMData")
-                .and_then(|data| data.get("tags"))
-                .and_then(|tags| serde_json::from_value(tags.clone()).ok())
-                .unwrap_or_default(),
-            setup_type: obj
-                .get("ACCSMData")
+            tags: match existing_data {
+                Some(data) => data
+                    .get("tags")
+                    .and_then(|tags| serde_json::from_value(tags.clone()).ok())
+                    .unwrap_or_default(),
+                None => default_tags,
+            },
+            setup_type: existing_data
                 .and_then(|data| data.get("setupType"))
                 .and_then(|t| t.as_str())
-                .unwrap_or("race")
-                .to_string(),
+                .map(|t| t.to_string())
+                .unwrap_or(default_setup_type),
+            ..AccsmData::default()
         };
 
-        obj.insert("ACCSMData".to_string(), serde_json::to_value(accsm_data)?);
+        // Keep notes and other metadata that saving doesn't manage
+        let mut merged_data = match existing_data {
+            Some(serde_json::Value::Object(data)) => data.clone(),
+            _ => serde_json::Map::new(),
+        };
+        if let serde_json::Value::Object(managed) = serde_json::to_value(accsm_data)? {
+            merged_data.extend(managed);
+        }
+        obj.insert(
+            "ACCSMData".to_string(),
+            serde_json::Value::Object(merged_data),
+        );
 
         // Ensure the directory structure exists
-        let dir_path = setups_path.join(&car_data.id).join(&track_data.id);
+        let dir_path = files::setup_dir(&setups_path, &car_data.id, &track_data.id);
 
         if !dir_path.exists() {
             fs::create_dir_all(&dir_path).map_err(|e| AccError::DirectoryCreationFailed {
@@ -391,38 +940,163 @@ impl AppStateManager {
             })?;
         }
 
+        // Keep the previous content in the history store before overwriting
+        if let Some(revision) =
+            history::snapshot(&setups_path, &car_data.id, &track_data.id, filename)?
+        {
+            debug!(
+                "Stored revision {} of {}/{}/{}",
+                revision.revision_id, car, track, filename
+            );
+        }
+
         // Write the file
         let file_path = dir_path.join(filename);
         let json_string = serde_json::to_string_pretty(&content)?;
 
-        fs::write(&file_path, json_string).map_err(|e| AccError::FileWriteFailed {
-            path: file_path.to_string_lossy().to_string(),
-            error: e.to_string(),
+        files::write_atomic(&file_path, json_string.as_bytes()).map_err(|e| {
+            AccError::FileWriteFailed {
+                path: file_path.to_string_lossy().to_string(),
+                error: e.to_string(),
+            }
         })?;
 
+        self.record_own_write(&file_path).await;
+
         info!("Saved setup: {}/{}/{}", car, track, filename);
         Ok(())
     }
 
-    /// Delete a setup file
-    pub async fn delete_setup(&self, car: &str, track: &str, filename: &str) -> AccResult<()> {
+    /// Update the ACCSMData metadata of a setup, leaving the setup values
+    /// themselves untouched
+    pub async fn update_setup_metadata(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+        metadata: SetupMetadata,
+    ) -> AccResult<SetupInfo> {
+        validate_metadata(&metadata)?;
+
+        // Fill in the managed fields, falling back to the configured defaults
+        let (default_tags, default_setup_type) = {
+            let config = self.config.read().await;
+            (
+                config.default_tags.clone(),
+                config.default_setup_type.clone(),
+            )
+        };
+
+        let file_path = self
+            .edit_setup_file(car, track, filename, |obj| {
+                let data = accsm_data_mut(obj);
+
+                // Managed fields always hold a value, cleared ones take the defaults
+                let tags = match metadata.tags {
+                    Some(tags) => Some(tags.unwrap_or_default()),
+                    None if !data.contains_key("tags") => Some(default_tags),
+                    None => None,
+                };
+                let setup_type = match metadata.setup_type {
+                    Some(setup_type) => Some(setup_type.unwrap_or(default_setup_type)),
+                    None if !data.contains_key("setupType") => Some(default_setup_type),
+                    None => None,
+                };
+
+                if !data.contains_key("id") {
+                    data.insert("id".to_string(), Uuid::new_v4().to_string().into());
+                }
+                data.insert(
+                    "lastModified".to_string(),
+                    serde_json::to_value(Utc::now())?,
+                );
+                if let Some(tags) = tags {
+                    data.insert("tags".to_string(), serde_json::to_value(tags)?);
+                }
+                if let Some(setup_type) = setup_type {
+                    data.insert("setupType".to_string(), setup_type.into());
+                }
+                set_optional(data, "author", metadata.author)?;
+                set_optional(data, "notes", metadata.notes)?;
+                set_optional(data, "sourceUrl", metadata.source_url)?;
+                set_optional(data, "gameVersion", metadata.game_version)?;
+                set_optional(data, "conditions", metadata.conditions)?;
+                set_optional(data, "rating", metadata.rating)?;
+                Ok(())
+            })
+            .await?;
+
+        info!("Updated metadata of {}/{}/{}", car, track, filename);
+        let (setup_info, _) = self.get_setup_info(&file_path, filename).await?;
+        Ok(setup_info)
+    }
+
+    /// Read a setup file as raw JSON, apply `edit` to its top-level object and
+    /// write it back, keeping the previous content in the history store.
+    /// Whatever `edit` leaves alone is written back exactly as it was read.
+    /// Returns the path of the file.
+    async fn edit_setup_file<F>(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+        edit: F,
+    ) -> AccResult<PathBuf>
+    where
+        F: FnOnce(&mut serde_json::Map<String, serde_json::Value>) -> AccResult<()>,
+    {
         let setups_path = self.get_setups_path().await;
-        let cars_data = crate::data::get_cars();
-        let tracks_data = crate::data::get_tracks();
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
+        let file_path = files::setup_path(&setups_path, &car_data.id, &track_data.id, filename)?;
+
+        if !file_path.exists() {
+            return Err(AccError::FileNotFound {
+                path: file_path.to_string_lossy().to_string(),
+            });
+        }
 
-        let car_data = cars_data.get(car).ok_or_else(|| AccError::InvalidCarId {
-            car_id: car.to_string(),
+        let raw = fs::read_to_string(&file_path).map_err(|e| AccError::IoError {
+            message: format!("Failed to read setup file: {}", e),
         })?;
-        let track_data = tracks_data
-            .get(track)
-            .ok_or_else(|| AccError::InvalidTrackId {
-                track_id: track.to_string(),
+        let mut content: serde_json::Value =
+            serde_json::from_str(&raw).map_err(|e| AccError::InvalidSetupJson {
+                file_path: file_path.to_string_lossy().to_string(),
+                error: e.to_string(),
+            })?;
+        let obj = content
+            .as_object_mut()
+            .ok_or_else(|| AccError::SetupValidationFailed {
+                reason: "Setup content must be a JSON object".to_string(),
             })?;
+        edit(obj)?;
+
+        if let Some(revision) =
+            history::snapshot(&setups_path, &car_data.id, &track_data.id, filename)?
+        {
+            debug!(
+                "Stored revision {} of {}/{}/{}",
+                revision.revision_id, car, track, filename
+            );
+        }
 
-        let file_path = setups_path
-            .join(&car_data.id)
-            .join(&track_data.id)
-            .join(filename);
+        let json_string = serde_json::to_string_pretty(&content)?;
+        files::write_atomic(&file_path, json_string.as_bytes()).map_err(|e| {
+            AccError::FileWriteFailed {
+                path: file_path.to_string_lossy().to_string(),
+                error: e.to_string(),
+            }
+        })?;
+        self.record_own_write(&file_path).await;
+
+        Ok(file_path)
+    }
+
+    /// Delete a setup file
+    pub async fn delete_setup(&self, car: &str, track: &str, filename: &str) -> AccResult<()> {
+        let setups_path = self.get_setups_path().await;
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
+
+        let file_path = files::setup_path(&setups_path, &car_data.id, &track_data.id, filename)?;
 
         if !file_path.exists() {
             return Err(AccError::FileNotFound {
@@ -430,19 +1104,1132 @@ impl AppStateManager {
             });
         }
 
-        fs::remove_file(&file_path).map_err(|e| AccError::IoError {
-            message: format!("Failed to delete setup file: {}", e),
-        })?;
+        let entry = trash::move_to_trash(&setups_path, &car_data.id, &track_data.id, filename)?;
+        self.record_own_write(&file_path).await;
 
-        info!("Deleted setup: {}/{}/{}", car, track, filename);
+        info!(
+            "Deleted setup: {}/{}/{} (trash id {})",
+            car, track, filename, entry.id
+        );
         Ok(())
     }
-}
 
-/// Create the global state manager instance
+    /// List every tag used in the library with the number of setups using it
+    pub async fn list_tags(&self) -> AccResult<Vec<TagUsage>> {
+        let structure = self.get_folder_structure().await?;
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for setup in structure
+            .cars
+            .iter()
+            .flat_map(|c| &c.tracks)
+            .flat_map(|t| &t.setups)
+            .filter(|s| s.managed)
+        {
+            let unique: HashSet<&String> = setup.tags.iter().collect();
+            for tag in unique {
+                *counts.entry(tag.clone()).or_default() += 1;
+            }
+        }
+
+        let mut tags: Vec<TagUsage> = counts
+            .into_iter()
+            .map(|(tag, count)| TagUsage { tag, count })
+            .collect();
+        tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+        Ok(tags)
+    }
+
+    /// Rename a tag in every setup that uses it
+    pub async fn rename_tag(&self, from: &str, to: &str) -> AccResult<TagUpdateReport> {
+        self.merge_tags(&[from.to_string()], to).await
+    }
+
+    /// Replace all the given tags with a single target tag in every setup
+    pub async fn merge_tags(&self, sources: &[String], target: &str) -> AccResult<TagUpdateReport> {
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(AccError::SetupValidationFailed {
+                reason: "Tag must not be empty".to_string(),
+            });
+        }
+
+        self.rewrite_tags(|tags| {
+            let mut updated = Vec::with_capacity(tags.len());
+            for tag in tags {
+                let tag = if sources.contains(tag) {
+                    target.to_string()
+                } else {
+                    tag.clone()
+                };
+                if !updated.contains(&tag) {
+                    updated.push(tag);
+                }
+            }
+            updated
+        })
+        .await
+    }
+
+    /// Remove a tag from every setup that uses it
+    pub async fn delete_tag(&self, tag: &str) -> AccResult<TagUpdateReport> {
+        self.rewrite_tags(|tags| tags.iter().filter(|t| *t != tag).cloned().collect())
+            .await
+    }
+
+    /// Apply a tag edit to every managed setup in the library, rewriting the
+    /// files whose tags change. Plain game files are never given ACCSMData.
+    async fn rewrite_tags<F>(&self, edit: F) -> AccResult<TagUpdateReport>
+    where
+        F: Fn(&[String]) -> Vec<String>,
+    {
+        let structure = self.get_folder_structure().await?;
+        let mut report = TagUpdateReport::default();
+
+        for car in &structure.cars {
+            for track in &car.tracks {
+                for setup in track.setups.iter().filter(|s| s.managed) {
+                    let tags = edit(&setup.tags);
+                    if tags == setup.tags {
+                        continue;
+                    }
+
+                    let setup_ref = SetupRef {
+                        car: car.car_id.clone(),
+                        track: track.track_id.clone(),
+                        filename: setup.filename.clone(),
+                    };
+                    match self.write_tags(&setup_ref, tags).await {
+                        Ok(()) => report.changed.push(setup_ref),
+                        Err(e) => {
+                            warn!(
+                                "Failed to update tags of {}/{}/{}: {}",
+                                setup_ref.car, setup_ref.track, setup_ref.filename, e
+                            );
+                            report.failed.push(setup_ref);
+                        }
+                    }
+                }
+            }
+        }
+
+        info!(
+            "Updated tags in {} setups ({} failed)",
+            report.changed.len(),
+            report.failed.len()
+        );
+        Ok(report)
+    }
+
+    /// Replace the tags of a single setup file, leaving the rest of the file
+    /// untouched
+    async fn write_tags(&self, setup: &SetupRef, tags: Vec<String>) -> AccResult<()> {
+        self.edit_setup_file(&setup.car, &setup.track, &setup.filename, |obj| {
+            let data = accsm_data_mut(obj);
+            data.insert(
+                "lastModified".to_string(),
+                serde_json::to_value(Utc::now())?,
+            );
+            data.insert("tags".to_string(), serde_json::to_value(tags)?);
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Copy a setup to another car/track and/or filename. Name collisions are
+    /// resolved by auto-suffixing unless `overwrite` is set.
+    pub async fn copy_setup(
+        &self,
+        source: &SetupRef,
+        target: &SetupRef,
+        overwrite: bool,
+    ) -> AccResult<SetupTransfer> {
+        self.transfer_setup(source, self, target, overwrite, false)
+            .await
+    }
+
+    /// Move a setup to another car/track and/or filename, taking its history along
+    pub async fn move_setup(
+        &self,
+        source: &SetupRef,
+        target: &SetupRef,
+        overwrite: bool,
+    ) -> AccResult<SetupTransfer> {
+        self.transfer_setup(source, self, target, overwrite, true)
+            .await
+    }
+
+    /// Rename a setup within its car/track folder
+    pub async fn rename_setup(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+        new_filename: &str,
+        overwrite: bool,
+    ) -> AccResult<SetupTransfer> {
+        let source = SetupRef {
+            car: car.to_string(),
+            track: track.to_string(),
+            filename: filename.to_string(),
+        };
+        let target = SetupRef {
+            filename: new_filename.to_string(),
+            ..source.clone()
+        };
+        self.move_setup(&source, &target, overwrite).await
+    }
+
+    /// Copy a setup from this library into another one
+    pub async fn copy_setup_to(
+        &self,
+        source: &SetupRef,
+        target_library: &Library,
+        target: &SetupRef,
+        overwrite: bool,
+    ) -> AccResult<SetupTransfer> {
+        self.transfer_setup(source, target_library, target, overwrite, false)
+            .await
+    }
+
+    /// Shared implementation of copy/move/rename. Moves are only supported
+    /// within a single library, since history lives next to the files.
+    async fn transfer_setup(
+        &self,
+        source: &SetupRef,
+        target_library: &Library,
+        target: &SetupRef,
+        overwrite: bool,
+        remove_source: bool,
+    ) -> AccResult<SetupTransfer> {
+        let same_library = std::ptr::eq(self, target_library);
+        if remove_source && !same_library {
+            return Err(AccError::SetupValidationFailed {
+                reason: "Setups can only be moved within a library".to_string(),
+            });
+        }
+
+        let setups_path = self.get_setups_path().await;
+        let target_setups_path = target_library.get_setups_path().await;
+        let (source_car, source_track) = resolve_car_and_track(&source.car, &source.track)?;
+        let (target_car, target_track) = resolve_car_and_track(&target.car, &target.track)?;
+
+        let source_path = files::setup_path(
+            &setups_path,
+            &source_car.id,
+            &source_track.id,
+            &source.filename,
+        )?;
+
+        if !source_path.exists() {
+            return Err(AccError::FileNotFound {
+                path: source_path.to_string_lossy().to_string(),
+            });
+        }
+
+        let target_dir = files::setup_dir(&target_setups_path, &target_car.id, &target_track.id);
+        let mut filename = with_json_extension(&target.filename);
+        files::check_file_name(&filename)?;
+        let same_file = same_library
+            && source_car.id == target_car.id
+            && source_track.id == target_track.id
+            && source.filename == filename;
+
+        if same_file && remove_source {
+            // Moving a file onto itself is a no-op
+            return Ok(SetupTransfer {
+                car: target_car.id.clone(),
+                track: target_track.id.clone(),
+                filename,
+                warnings: Vec::new(),
+            });
+        }
+
+        if target_dir.join(&filename).exists() && (!overwrite || same_file) {
+            filename = next_free_filename(&target_dir, &filename);
+        }
+
+        let raw = fs::read_to_string(&source_path).map_err(|e| AccError::IoError {
+            message: format!("Failed to read setup file: {}", e),
+        })?;
+        let mut content: serde_json::Value =
+            serde_json::from_str(&raw).map_err(|e| AccError::InvalidSetupJson {
+                file_path: source_path.to_string_lossy().to_string(),
+                error: e.to_string(),
+            })?;
+
+        // Cross-car copies keep going but report what doesn't fit the new car
+        let warnings = if source_car.id != target_car.id {
+            let mut retargeted = content.clone();
+            if let Some(obj) = retargeted.as_object_mut() {
+                obj.insert(
+                    "carName".to_string(),
+                    serde_json::Value::String(target_car.id.clone()),
+                );
+            }
+            validation::validate_setup(&target_car.id, &retargeted).violations
+        } else {
+            Vec::new()
+        };
+
+        if !warnings.is_empty() {
+            warn!(
+                "{} parameters are out of range for {}",
+                warnings.len(),
+                target_car.id
+            );
+        }
+
+        if remove_source {
+            // The moved file keeps its id, so release it from the source first
+            self.search_index.write().await.remove(
+                &source_car.id,
+                &source_track.id,
+                &source.filename,
+            );
+        } else if let Some(accsm_data) = content
+            .get_mut("ACCSMData")
+            .and_then(|data| data.as_object_mut())
+        {
+            // A copy is a new setup and gets its own id
+            accsm_data.remove("id");
+        }
+
+        // save_setup rewrites carName, snapshots any overwritten file and writes atomically
+        target_library
+            .save_setup(&target_car.id, &target_track.id, &filename, content)
+            .await?;
+
+        if remove_source {
+            fs::remove_file(&source_path).map_err(|e| AccError::IoError {
+                message: format!("Failed to remove moved setup file: {}", e),
+            })?;
+            self.record_own_write(&source_path).await;
+            history::move_revisions(
+                &setups_path,
+                (&source_car.id, &source_track.id, &source.filename),
+                (&target_car.id, &target_track.id, &filename),
+            )?;
+        }
+
+        info!(
+            "{} setup {}/{}/{} -> {}/{}/{}",
+            if remove_source { "Moved" } else { "Copied" },
+            source_car.id,
+            source_track.id,
+            source.filename,
+            target_car.id,
+            target_track.id,
+            filename
+        );
+
+        Ok(SetupTransfer {
+            car: target_car.id.clone(),
+            track: target_track.id.clone(),
+            filename,
+            warnings,
+        })
+    }
+
+    /// Import setup files and folders into the library. Cars come from each
+    /// file's `carName` and tracks are inferred from the path unless
+    /// `track_override` is given. With `commit` unset only the plan is returned.
+    pub async fn import_setups(
+        &self,
+        paths: &[PathBuf],
+        track_override: Option<&str>,
+        overwrite: bool,
+        commit: bool,
+    ) -> AccResult<ImportReport> {
+        let setups_path = self.get_setups_path().await;
+
+        let track_override = match track_override {
+            Some(track) => {
+                Some(
+                    crate::data::get_track(track).ok_or_else(|| AccError::InvalidTrackId {
+                        track_id: track.to_string(),
+                    })?,
+                )
+            }
+            None => None,
+        };
+
+        let files = import::collect_files(paths);
+        let plan = import::plan_import(&setups_path, &files, track_override, overwrite);
+
+        let mut items = Vec::with_capacity(plan.len());
+        for (mut item, content) in plan {
+            if let (true, Some(content)) = (commit, content) {
+                let car = item.car.clone().unwrap_or_default();
+                let track = item.track.clone().unwrap_or_default();
+                let filename = item.filename.clone().unwrap_or_default();
+
+                match self.save_setup(&car, &track, &filename, content).await {
+                    Ok(()) => item.status = ImportStatus::Imported,
+                    Err(e) => {
+                        warn!("Failed to import {}: {}", item.source_path, e);
+                        item.status = ImportStatus::Invalid;
+                        item.reason = Some(e.to_string());
+                    }
+                }
+            }
+            items.push(item);
+        }
+
+        info!(
+            "Import {}: {} files processed",
+            if commit { "committed" } else { "planned" },
+            items.len()
+        );
+
+        Ok(ImportReport {
+            committed: commit,
+            items,
+        })
+    }
+
+    /// Export the selected setups into a zip pack laid out like the ACC folder
+    pub async fn export_setups(&self, selection: &[SetupRef], dest: &Path) -> AccResult<usize> {
+        let setups_path = self.get_setups_path().await;
+
+        let resolved = selection
+            .iter()
+            .map(|setup| {
+                let (car_data, track_data) = resolve_car_and_track(&setup.car, &setup.track)?;
+                Ok(SetupRef {
+                    car: car_data.id.clone(),
+                    track: track_data.id.clone(),
+                    filename: setup.filename.clone(),
+                })
+            })
+            .collect::<AccResult<Vec<_>>>()?;
+
+        archive::export_setups(&setups_path, &resolved, dest)
+    }
+
+    /// Import a zip pack laid out as `<car>/<track>/<file>.json`. Conflicts
+    /// are reported against the files on disk. With `commit` unset only the
+    /// plan is returned.
+    pub async fn import_setup_pack(
+        &self,
+        zip_path: &Path,
+        overwrite: bool,
+        commit: bool,
+    ) -> AccResult<ImportReport> {
+        let setups_path = self.get_setups_path().await;
+        let entries = archive::read_pack(zip_path)?;
+
+        let mut planned = HashSet::new();
+        let mut items = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            let mut item = ImportItem {
+                source_path: entry.name.clone(),
+                status: ImportStatus::Invalid,
+                car: None,
+                track: None,
+                filename: None,
+                reason: None,
+                aliases: Vec::new(),
+                violations: Vec::new(),
+            };
+
+            if !entry.name.ends_with(".json") {
+                item.status = ImportStatus::Skipped;
+                item.reason = Some("Not a JSON file".to_string());
+                items.push(item);
+                continue;
+            }
+
+            let content = match entry.content {
+                Ok(content) => content,
+                Err(reason) => {
+                    item.reason = Some(reason);
+                    items.push(item);
+                    continue;
+                }
+            };
+
+            let Some((car_folder, track_folder, filename)) = entry.location else {
+                item.reason =
+                    Some("Entry is not laid out as <car>/<track>/<file>.json".to_string());
+                items.push(item);
+                continue;
+            };
+            item.filename = Some(filename.clone());
+
+            let Some((car_data, car_alias)) = resolve_car(&car_folder) else {
+                item.reason = Some(format!("Unknown car folder '{}'", car_folder));
+                items.push(item);
+                continue;
+            };
+            item.car = Some(car_data.id.clone());
+            item.aliases.extend(car_alias);
+
+            let Some((track_data, track_alias)) = resolve_track(&track_folder)
+                .or_else(|| import::match_track_name(&track_folder).map(|track| (track, None)))
+            else {
+                item.reason = Some(format!("Unknown track folder '{}'", track_folder));
+                items.push(item);
+                continue;
+            };
+            item.track = Some(track_data.id.clone());
+            item.aliases.extend(track_alias);
+
+            let value: serde_json::Value = match serde_json::from_str(&content) {
+                Ok(value) => value,
+                Err(e) => {
+                    item.reason = Some(format!("Invalid JSON: {}", e));
+                    items.push(item);
+                    continue;
+                }
+            };
+
+            let json_car = value.get("carName").and_then(|v| v.as_str()).unwrap_or("");
+            if !resolve_car(json_car).is_some_and(|(car, _)| car.id == car_data.id) {
+                item.reason = Some(
+                    AccError::CarNameMismatch {
+                        json_car: json_car.to_string(),
+                        folder_car: car_data.id.clone(),
+                    }
+                    .to_string(),
+                );
+                items.push(item);
+                continue;
+            }
+
+            let validation = validation::validate_setup(&car_data.id, &value);
+            for alias in validation.aliases {
+                validation::push_alias(&mut item.aliases, alias);
+            }
+            let violations = validation.violations;
+            if !violations.is_empty() {
+                item.reason = Some(format!(
+                    "Setup has {} invalid parameter(s)",
+                    violations.len()
+                ));
+                item.violations = violations;
+                items.push(item);
+                continue;
+            }
+
+            let key = (car_data.id.clone(), track_data.id.clone(), filename.clone());
+            if planned.contains(&key) {
+                item.status = ImportStatus::Conflict;
+                item.reason = Some("Another file in this pack has the same target".to_string());
+                items.push(item);
+                continue;
+            }
+            let target =
+                files::setup_dir(&setups_path, &car_data.id, &track_data.id).join(&filename);
+            if target.exists() && !overwrite {
+                item.status = ImportStatus::Conflict;
+                item.reason = Some("A setup with this name already exists".to_string());
+                items.push(item);
+                continue;
+            }
+            planned.insert(key);
+
+            item.status = ImportStatus::Ready;
+            if commit {
+                match self
+                    .save_setup(&car_data.id, &track_data.id, &filename, value)
+                    .await
+                {
+                    Ok(()) => item.status = ImportStatus::Imported,
+                    Err(e) => {
+                        warn!("Failed to import {}: {}", item.source_path, e);
+                        item.status = ImportStatus::Invalid;
+                        item.reason = Some(e.to_string());
+                    }
+                }
+            }
+            items.push(item);
+        }
+
+        info!(
+            "Setup pack import {}: {} entries processed",
+            if commit { "committed" } else { "planned" },
+            items.len()
+        );
+
+        Ok(ImportReport {
+            committed: commit,
+            items,
+        })
+    }
+
+    /// Get the number of days deleted setups are kept in the trash
+    pub async fn get_trash_retention_days(&self) -> u32 {
+        self.config.read().await.trash_retention_days
+    }
+
+    /// Permanently delete trashed setups that are older than the retention window
+    pub async fn purge_expired_trash(&self) -> AccResult<usize> {
+        let setups_path = self.get_setups_path().await;
+        let retention_days = self.get_trash_retention_days().await;
+        trash::purge_expired(&setups_path, retention_days)
+    }
+
+    /// List the setups currently in the trash
+    pub async fn list_trash(&self) -> AccResult<Vec<TrashEntry>> {
+        self.purge_expired_trash().await?;
+        let setups_path = self.get_setups_path().await;
+        trash::list_entries(&setups_path)
+    }
+
+    /// Restore a trashed setup to its original car/track folder
+    pub async fn restore_from_trash(&self, id: &str, overwrite: bool) -> AccResult<TrashEntry> {
+        let setups_path = self.get_setups_path().await;
+        let entry = trash::restore(&setups_path, id, overwrite)?;
+
+        let file_path =
+            files::setup_dir(&setups_path, &entry.car, &entry.track).join(&entry.filename);
+        self.record_own_write(&file_path).await;
+        Ok(entry)
+    }
+
+    /// Permanently delete everything in the trash
+    pub async fn empty_trash(&self) -> AccResult<usize> {
+        let setups_path = self.get_setups_path().await;
+        let removed = trash::empty(&setups_path)?;
+        info!("Emptied trash ({} items)", removed);
+        Ok(removed)
+    }
+
+    /// List the stored revisions of a setup file, newest first
+    pub async fn list_setup_history(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+    ) -> AccResult<Vec<SetupRevision>> {
+        let setups_path = self.get_setups_path().await;
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
+        history::list_revisions(&setups_path, &car_data.id, &track_data.id, filename)
+    }
+
+    /// Read a stored revision of a setup file
+    pub async fn read_setup_revision(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+        revision_id: &str,
+    ) -> AccResult<SetupFile> {
+        let setups_path = self.get_setups_path().await;
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
+        let content = history::read_revision(
+            &setups_path,
+            &car_data.id,
+            &track_data.id,
+            filename,
+            revision_id,
+        )?;
+
+        serde_json::from_str(&content).map_err(|e| AccError::InvalidSetupJson {
+            file_path: format!("{}/{}/{}@{}", car, track, filename, revision_id),
+            error: e.to_string(),
+        })
+    }
+
+    /// Diff two revisions of a setup file. When `to_revision` is `None` the
+    /// current file on disk is used as the newer side.
+    pub async fn diff_setup_revisions(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+        from_revision: &str,
+        to_revision: Option<&str>,
+    ) -> AccResult<Vec<ParameterChange>> {
+        let old_setup = self
+            .read_setup_revision(car, track, filename, from_revision)
+            .await?;
+        let new_setup = match to_revision {
+            Some(revision_id) => {
+                self.read_setup_revision(car, track, filename, revision_id)
+                    .await?
+            }
+            None => self.read_setup(car, track, filename).await?,
+        };
+
+        crate::diff::diff_setups(&old_setup, &new_setup)
+    }
+
+    /// Restore a stored revision over the current file. The current content
+    /// is snapshotted first, so a restore can itself be undone.
+    pub async fn restore_setup_revision(
+        &self,
+        car: &str,
+        track: &str,
+        filename: &str,
+        revision_id: &str,
+    ) -> AccResult<()> {
+        let setups_path = self.get_setups_path().await;
+        let (car_data, track_data) = resolve_car_and_track(car, track)?;
+        let content = history::read_revision(
+            &setups_path,
+            &car_data.id,
+            &track_data.id,
+            filename,
+            revision_id,
+        )?;
+
+        let value: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| AccError::InvalidSetupJson {
+                file_path: format!("{}/{}/{}@{}", car, track, filename, revision_id),
+                error: e.to_string(),
+            })?;
+
+        self.save_setup(car, track, filename, value).await?;
+        info!(
+            "Restored revision {} of {}/{}/{}",
+            revision_id, car, track, filename
+        );
+        Ok(())
+    }
+}
+
+/// Insert or replace a setup listing entry, creating its car/track folders
+fn upsert_setup_info(structure: &mut FolderStructure, car: &Car, track: &Track, info: SetupInfo) {
+    let car_index = match structure.cars.iter().position(|c| c.car_id == car.id) {
+        Some(index) => index,
+        None => {
+            structure.cars.push(CarFolder {
+                car_id: car.id.clone(),
+                car_name: car.pretty_name.clone(),
+                alias: None,
+                tracks: Vec::new(),
+            });
+            structure.cars.sort_by(|a, b| a.car_name.cmp(&b.car_name));
+            structure
+                .cars
+                .iter()
+                .position(|c| c.car_id == car.id)
+                .unwrap_or_default()
+        }
+    };
+    let car_folder = &mut structure.cars[car_index];
+
+    let track_index = match car_folder
+        .tracks
+        .iter()
+        .position(|t| t.track_id == track.id)
+    {
+        Some(index) => index,
+        None => {
+            car_folder.tracks.push(TrackFolder {
+                track_id: track.id.clone(),
+                track_name: track.pretty_name.clone(),
+                alias: None,
+                setups: Vec::new(),
+            });
+            car_folder
+                .tracks
+                .sort_by(|a, b| a.track_name.cmp(&b.track_name));
+            car_folder
+                .tracks
+                .iter()
+                .position(|t| t.track_id == track.id)
+                .unwrap_or_default()
+        }
+    };
+    let setups = &mut car_folder.tracks[track_index].setups;
+
+    setups.retain(|s| s.filename != info.filename);
+    setups.push(info);
+    setups.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+}
+
+/// Remove a setup listing entry, dropping its track folder if it is now
+/// empty to match what a full scan produces. Returns whether anything changed.
+fn remove_setup_info(
+    structure: &mut FolderStructure,
+    car_id: &str,
+    track_id: &str,
+    filename: &str,
+) -> bool {
+    let Some(car_folder) = structure.cars.iter_mut().find(|c| c.car_id == car_id) else {
+        return false;
+    };
+    let Some(track_folder) = car_folder
+        .tracks
+        .iter_mut()
+        .find(|t| t.track_id == track_id)
+    else {
+        return false;
+    };
+
+    let before = track_folder.setups.len();
+    track_folder.setups.retain(|s| s.filename != filename);
+    let removed = track_folder.setups.len() != before;
+
+    car_folder.tracks.retain(|t| !t.setups.is_empty());
+    removed
+}
+
+/// Look up the car and track metadata for a pair of ids
+fn resolve_car_and_track(car: &str, track: &str) -> AccResult<(&'static Car, &'static Track)> {
+    let car_data = crate::data::get_car(car).ok_or_else(|| AccError::InvalidCarId {
+        car_id: car.to_string(),
+    })?;
+    let track_data = crate::data::get_track(track).ok_or_else(|| AccError::InvalidTrackId {
+        track_id: track.to_string(),
+    })?;
+    Ok((car_data, track_data))
+}
+
+/// Check user-provided metadata before it is written
+fn validate_metadata(metadata: &SetupMetadata) -> AccResult<()> {
+    if metadata
+        .rating
+        .flatten()
+        .is_some_and(|rating| !(1..=5).contains(&rating))
+    {
+        return Err(AccError::SetupValidationFailed {
+            reason: "Rating must be between 1 and 5".to_string(),
+        });
+    }
+
+    if let Some(Some(conditions)) = &metadata.conditions {
+        let ranges = [
+            ("air", conditions.air_temp_min, conditions.air_temp_max),
+            (
+                "track",
+                conditions.track_temp_min,
+                conditions.track_temp_max,
+            ),
+        ];
+        for (name, min, max) in ranges {
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    return Err(AccError::SetupValidationFailed {
+                        reason: format!("Minimum {} temperature is above the maximum", name),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The ACCSMData object of a setup, created if missing or not an object
+fn accsm_data_mut(
+    obj: &mut serde_json::Map<String, serde_json::Value>,
+) -> &mut serde_json::Map<String, serde_json::Value> {
+    let data = obj
+        .entry("ACCSMData")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if !data.is_object() {
+        *data = serde_json::Value::Object(serde_json::Map::new());
+    }
+    data.as_object_mut().unwrap()
+}
+
+/// Apply an optional ACCSMData field update: `None` keeps the field as it is,
+/// `Some(None)` removes it and `Some(Some(value))` sets it
+fn set_optional<T: serde::Serialize>(
+    data: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    value: Option<Option<T>>,
+) -> AccResult<()> {
+    match value {
+        Some(Some(value)) => {
+            data.insert(key.to_string(), serde_json::to_value(value)?);
+        }
+        Some(None) => {
+            data.remove(key);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+fn file_stamp(file_path: &Path) -> FileStamp {
+    let metadata = fs::metadata(file_path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// Append the `.json` extension if a filename doesn't have it yet
+fn with_json_extension(filename: &str) -> String {
+    if filename.ends_with(".json") {
+        filename.to_string()
+    } else {
+        format!("{}.json", filename)
+    }
+}
+
+/// Find a free `<name> (n).json` filename inside a directory
+fn next_free_filename(dir_path: &Path, filename: &str) -> String {
+    let stem = filename.strip_suffix(".json").unwrap_or(filename);
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{} ({}).json", stem, counter);
+        if !dir_path.join(&candidate).exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Application state manager that owns the setup libraries and the config
+pub struct AppStateManager {
+    libraries: RwLock<Vec<Arc<Library>>>,
+    config: Arc<ConfigStore>,
+}
+
+impl AppStateManager {
+    /// Create a state manager with a library for every configured folder
+    pub fn new(config: Arc<ConfigStore>, library_configs: &[LibraryConfig]) -> Self {
+        let libraries = library_configs
+            .iter()
+            .map(|library| {
+                Arc::new(Library::new(
+                    library.id.clone(),
+                    library.path.clone(),
+                    Arc::clone(&config),
+                ))
+            })
+            .collect();
+
+        Self {
+            libraries: RwLock::new(libraries),
+            config,
+        }
+    }
+
+    /// Get a library by id. `None` selects the live ACC library, or the
+    /// first library if none is marked live.
+    pub async fn library(&self, id: Option<&str>) -> AccResult<Arc<Library>> {
+        let libraries = self.libraries.read().await;
+        let library = match id {
+            Some(id) => libraries.iter().find(|library| library.id == id),
+            None => {
+                let config = self.config.read().await;
+                let live_id = config.libraries.iter().find(|l| l.live).map(|l| &l.id);
+                libraries
+                    .iter()
+                    .find(|library| Some(&library.id) == live_id)
+                    .or_else(|| libraries.first())
+            }
+        };
+
+        library.cloned().ok_or_else(|| AccError::LibraryNotFound {
+            library_id: id.unwrap_or_default().to_string(),
+        })
+    }
+
+    /// Get every library
+    pub async fn libraries(&self) -> Vec<Arc<Library>> {
+        self.libraries.read().await.clone()
+    }
+
+    /// List the configured libraries
+    pub async fn list_libraries(&self) -> Vec<LibraryConfig> {
+        self.config.read().await.libraries.clone()
+    }
+
+    /// Register a new library for an existing folder
+    pub async fn add_library(
+        &self,
+        name: &str,
+        path: PathBuf,
+        live: bool,
+    ) -> AccResult<(LibraryConfig, Arc<Library>)> {
+        if !path.is_dir() {
+            return Err(AccError::SetupsFolderNotFound {
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+
+        let mut libraries = self.libraries.write().await;
+        let id = unique_library_id(name, &libraries);
+        let library_config = LibraryConfig {
+            id: id.clone(),
+            name: name.to_string(),
+            path: path.clone(),
+            live,
+        };
+
+        let entry = library_config.clone();
+        self.config
+            .update(move |config| {
+                if entry.live {
+                    config.libraries.iter_mut().for_each(|l| l.live = false);
+                }
+                config.libraries.push(entry);
+            })
+            .await?;
+
+        let library = Arc::new(Library::new(id, path, Arc::clone(&self.config)));
+        libraries.push(Arc::clone(&library));
+        info!("Added library '{}' ({})", name, library.id);
+        Ok((library_config, library))
+    }
+
+    /// Stop managing a library. Files on disk are left untouched.
+    pub async fn remove_library(&self, id: &str) -> AccResult<()> {
+        let mut libraries = self.libraries.write().await;
+        if !libraries.iter().any(|library| library.id == id) {
+            return Err(AccError::LibraryNotFound {
+                library_id: id.to_string(),
+            });
+        }
+        if libraries.len() == 1 {
+            return Err(AccError::SetupValidationFailed {
+                reason: "The last library cannot be removed".to_string(),
+            });
+        }
+
+        let library_id = id.to_string();
+        self.config
+            .update(move |config| config.libraries.retain(|l| l.id != library_id))
+            .await?;
+        libraries.retain(|library| library.id != id);
+        info!("Removed library {}", id);
+        Ok(())
+    }
+
+    /// Mark a library as the folder ACC reads setups from
+    pub async fn set_live_library(&self, id: &str) -> AccResult<()> {
+        // Validate the id first
+        self.library(Some(id)).await?;
+
+        let library_id = id.to_string();
+        self.config
+            .update(move |config| {
+                for library in &mut config.libraries {
+                    library.live = library.id == library_id;
+                }
+            })
+            .await?;
+        info!("Marked library {} as live", id);
+        Ok(())
+    }
+
+    /// Copy a setup from one library into another
+    pub async fn copy_between_libraries(
+        &self,
+        source_library: &str,
+        source: &SetupRef,
+        target_library: &str,
+        target: &SetupRef,
+        overwrite: bool,
+    ) -> AccResult<SetupTransfer> {
+        let source_lib = self.library(Some(source_library)).await?;
+        let target_lib = self.library(Some(target_library)).await?;
+        source_lib
+            .copy_setup_to(source, &target_lib, target, overwrite)
+            .await
+    }
+
+    /// Get a copy of the current app configuration
+    pub async fn get_config(&self) -> AppConfig {
+        self.config.read().await.clone()
+    }
+
+    /// Replace the app configuration and persist it. Libraries are managed
+    /// through their own commands and are kept as they are.
+    pub async fn update_config(&self, new_config: AppConfig) -> AccResult<AppConfig> {
+        self.config
+            .update(move |config| {
+                let version = config.version;
+                let libraries = std::mem::take(&mut config.libraries);
+                *config = AppConfig {
+                    version,
+                    libraries,
+                    ..new_config
+                };
+            })
+            .await?;
+
+        self.purge_expired_trash().await?;
+        info!("Updated app configuration");
+        Ok(self.get_config().await)
+    }
+
+    /// Set the quiet period the watchers wait for before processing events
+    pub async fn set_watcher_debounce_ms(&self, debounce_ms: u64) -> AccResult<()> {
+        self.config
+            .update(|config| config.watcher_debounce_ms = debounce_ms)
+            .await
+    }
+
+    /// Get the number of days deleted setups are kept in the trash
+    pub async fn get_trash_retention_days(&self) -> u32 {
+        self.config.read().await.trash_retention_days
+    }
+
+    /// Set the number of days deleted setups are kept in the trash and purge
+    /// anything that is now past the window. Zero keeps items forever.
+    pub async fn set_trash_retention_days(&self, days: u32) -> AccResult<()> {
+        self.config
+            .update(|config| config.trash_retention_days = days)
+            .await?;
+        self.purge_expired_trash().await?;
+        Ok(())
+    }
+
+    /// Purge expired trash items in every library
+    pub async fn purge_expired_trash(&self) -> AccResult<usize> {
+        let mut purged = 0;
+        for library in self.libraries().await {
+            purged += library.purge_expired_trash().await?;
+        }
+        Ok(purged)
+    }
+}
+
+/// Derive a unique library id from its display name
+fn unique_library_id(name: &str, libraries: &[Arc<Library>]) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let base = match slug.trim_matches('_') {
+        "" => "library".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+
+    let mut id = base.clone();
+    let mut counter = 2;
+    while libraries.iter().any(|library| library.id == id) {
+        id = format!("{}_{}", base, counter);
+        counter += 1;
+    }
+    id
+}
+
+/// Create the global state manager instance
 pub fn create_state_manager() -> Arc<AppStateManager> {
-    let default_path = get_default_setups_path();
-    Arc::new(AppStateManager::new(default_path))
+    let config_path = config::config_file_path();
+    let mut app_config = config_path.as_deref().map(config::load).unwrap_or_default();
+
+    // First start: manage the ACC setups folder as the live library
+    if app_config.libraries.is_empty() {
+        app_config.libraries.push(LibraryConfig {
+            id: config::DEFAULT_LIBRARY_ID.to_string(),
+            name: config::DEFAULT_LIBRARY_NAME.to_string(),
+            path: get_default_setups_path(),
+            live: true,
+        });
+    }
+
+    let library_configs = app_config.libraries.clone();
+    for library in &library_configs {
+        info!("Using library '{}' at {:?}", library.name, library.path);
+    }
+
+    let config = Arc::new(ConfigStore::new(app_config, config_path));
+    Arc::new(AppStateManager::new(config, &library_configs))
 }
 
 /// Get the default setups path based on the platform
@@ -461,3 +2248,86 @@ fn get_default_setups_path() -> PathBuf {
             .unwrap_or_else(|| PathBuf::from("./acc_setups_test"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    /// Fresh library folder under the system temp dir
+    fn temp_library(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("accsm_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[tokio::test]
+    async fn assigning_an_id_keeps_the_key_order() {
+        let root = temp_library("key_order");
+        let dir = root.join("bmw_m4_gt3").join("monza");
+        fs::create_dir_all(&dir).unwrap();
+        let original = r#"{
+  "carName": "bmw_m4_gt3",
+  "basicSetup": {
+    "tyres": {"tyreCompound": 0, "tyrePressure": [55, 56, 57, 58], "zUnknown": 1, "aUnknown": 2},
+    "alignment": {"toe": [40, 40, 40, 40], "camber": [10, 10, 10, 10]}
+  },
+  "advancedSetup": {"mechanicalBalance": {"aRBFront": 3}},
+  "trackBopType": 4,
+  "ACCSMData": {"lastModified": "2026-01-01T00:00:00Z", "tags": [], "setupType": "race"}
+}"#;
+        fs::write(dir.join("race.json"), original).unwrap();
+
+        let config = Arc::new(ConfigStore::new(AppConfig::default(), None));
+        let library = Library::new("test".to_string(), root.clone(), config);
+        library.refresh_folder_structure().await.unwrap();
+
+        let written = fs::read_to_string(dir.join("race.json")).unwrap();
+        let mut written: serde_json::Value = serde_json::from_str(&written).unwrap();
+        assert!(written["ACCSMData"]
+            .as_object_mut()
+            .unwrap()
+            .remove("id")
+            .is_some());
+        let original: serde_json::Value = serde_json::from_str(original).unwrap();
+        assert_eq!(
+            serde_json::to_string(&written).unwrap(),
+            serde_json::to_string(&original).unwrap()
+        );
+
+        // Unknown keys keep their order through the typed model as well
+        let setup: SetupFile = serde_json::from_value(original).unwrap();
+        let keys: Vec<&String> = setup
+            .basic_setup
+            .tyres
+            .as_ref()
+            .unwrap()
+            .other_fields
+            .keys()
+            .collect();
+        assert_eq!(keys, ["zUnknown", "aUnknown"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn own_writes_are_forgotten_once_the_file_changes_again() {
+        let root = temp_library("own_write");
+        let file_path = root.join("race.json");
+        fs::write(&file_path, "{}").unwrap();
+
+        let config = Arc::new(ConfigStore::new(AppConfig::default(), None));
+        let library = Library::new("test".to_string(), root.clone(), config);
+        library.mark_own_write(&file_path);
+
+        // A single write raises several events
+        assert!(library.is_own_write(&file_path));
+        assert!(library.is_own_write(&file_path));
+
+        fs::write(&file_path, "{\"changed\": true}").unwrap();
+        assert!(!library.is_own_write(&file_path));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+use crate::errors::{AccError, AccResult};
+use crate::files;
+use crate::history::{self, APP_DIR_NAME};
+use crate::models::TrashEntry;
+use chrono::{Duration, Utc};
+use log::{debug, info, warn};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// File holding the trashed setup content inside an item folder
+const SETUP_FILE_NAME: &str = "setup.json";
+
+/// File holding the original location of a trashed setup
+const ENTRY_FILE_NAME: &str = "entry.json";
+
+/// Default number of days a trashed setup is kept before being purged
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// Root folder of the trash inside the setups directory
+pub fn trash_dir(setups_path: &Path) -> PathBuf {
+    setups_path.join(APP_DIR_NAME).join("trash")
+}
+
+/// Move a setup file into the trash, recording where it came from
+pub fn move_to_trash(
+    setups_path: &Path,
+    car_id: &str,
+    track_id: &str,
+    filename: &str,
+) -> AccResult<TrashEntry> {
+    let file_path = files::setup_path(setups_path, car_id, track_id, filename)?;
+    if !file_path.exists() {
+        return Err(AccError::FileNotFound {
+            path: file_path.to_string_lossy().to_string(),
+        });
+    }
+
+    let root = trash_dir(setups_path);
+    fs::create_dir_all(&root).map_err(|e| AccError::DirectoryCreationFailed {
+        path: root.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })?;
+
+    let deleted_at = Utc::now();
+    let id = Uuid::new_v4().to_string();
+    let item_dir = root.join(&id);
+    fs::create_dir_all(&item_dir).map_err(|e| AccError::DirectoryCreationFailed {
+        path: item_dir.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })?;
+
+    let entry = TrashEntry {
+        id,
+        car: car_id.to_string(),
+        track: track_id.to_string(),
+        filename: filename.to_string(),
+        deleted_at,
+    };
+
+    let entry_path = item_dir.join(ENTRY_FILE_NAME);
+    let entry_json = serde_json::to_string_pretty(&entry)?;
+    files::write_atomic(&entry_path, entry_json.as_bytes()).map_err(|e| {
+        AccError::FileWriteFailed {
+            path: entry_path.to_string_lossy().to_string(),
+            error: e.to_string(),
+        }
+    })?;
+
+    let trashed_path = item_dir.join(SETUP_FILE_NAME);
+    if let Err(e) = fs::rename(&file_path, &trashed_path) {
+        // Leave nothing half-done behind if the move itself failed
+        let _ = fs::remove_dir_all(&item_dir);
+        return Err(AccError::IoError {
+            message: format!("Failed to move setup file to trash: {}", e),
+        });
+    }
+
+    debug!(
+        "Moved {}/{}/{} to trash as {}",
+        car_id, track_id, filename, entry.id
+    );
+    Ok(entry)
+}
+
+/// List all items currently in the trash, most recently deleted first
+pub fn list_entries(setups_path: &Path) -> AccResult<Vec<TrashEntry>> {
+    let root = trash_dir(setups_path);
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&root).map_err(|e| AccError::IoError {
+        message: format!("Failed to read trash directory: {}", e),
+    })?;
+
+    let mut items = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| AccError::IoError {
+            message: format!("Failed to read trash entry: {}", e),
+        })?;
+
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        match read_entry(&path) {
+            Ok(item) => items.push(item),
+            Err(e) => warn!("Skipping unreadable trash item {:?}: {}", path, e),
+        }
+    }
+
+    items.sort_by_key(|item| Reverse(item.deleted_at));
+    Ok(items)
+}
+
+/// Move a trashed setup back to its original location
+pub fn restore(setups_path: &Path, id: &str, overwrite: bool) -> AccResult<TrashEntry> {
+    // Ids come from the frontend and are joined into a path
+    if Uuid::parse_str(id).is_err() {
+        return Err(AccError::InvalidFileName {
+            name: id.to_string(),
+        });
+    }
+    let item_dir = trash_dir(setups_path).join(id);
+    if !item_dir.exists() {
+        return Err(AccError::FileNotFound {
+            path: item_dir.to_string_lossy().to_string(),
+        });
+    }
+
+    let entry = read_entry(&item_dir)?;
+    let dir_path = files::setup_dir(setups_path, &entry.car, &entry.track);
+    let file_path = dir_path.join(&entry.filename);
+
+    if file_path.exists() && !overwrite {
+        return Err(AccError::SetupAlreadyExists {
+            path: file_path.to_string_lossy().to_string(),
+        });
+    }
+
+    fs::create_dir_all(&dir_path).map_err(|e| AccError::DirectoryCreationFailed {
+        path: dir_path.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })?;
+
+    // Keep the setup being replaced in the history store
+    if let Some(revision) =
+        history::snapshot(setups_path, &entry.car, &entry.track, &entry.filename)?
+    {
+        debug!(
+            "Stored revision {} of {}/{}/{}",
+            revision.revision_id, entry.car, entry.track, entry.filename
+        );
+    }
+
+    fs::rename(item_dir.join(SETUP_FILE_NAME), &file_path).map_err(|e| AccError::IoError {
+        message: format!("Failed to restore setup from trash: {}", e),
+    })?;
+
+    fs::remove_dir_all(&item_dir).map_err(|e| AccError::IoError {
+        message: format!("Failed to remove restored trash item: {}", e),
+    })?;
+
+    info!(
+        "Restored {}/{}/{} from trash",
+        entry.car, entry.track, entry.filename
+    );
+    Ok(entry)
+}
+
+/// Permanently delete every item in the trash
+pub fn empty(setups_path: &Path) -> AccResult<usize> {
+    let items = list_entries(setups_path)?;
+    for item in &items {
+        remove_item(setups_path, &item.id)?;
+    }
+    Ok(items.len())
+}
+
+/// Permanently delete items older than the retention window.
+/// A retention of zero days keeps items forever.
+pub fn purge_expired(setups_path: &Path, retention_days: u32) -> AccResult<usize> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+
+    let cutoff = Utc::now() - Duration::days(i64::from(retention_days));
+    let mut purged = 0;
+    for item in list_entries(setups_path)? {
+        if item.deleted_at < cutoff {
+            remove_item(setups_path, &item.id)?;
+            purged += 1;
+        }
+    }
+
+    if purged > 0 {
+        info!("Purged {} expired items from trash", purged);
+    }
+    Ok(purged)
+}
+
+fn read_entry(item_dir: &Path) -> AccResult<TrashEntry> {
+    let entry_path = item_dir.join(ENTRY_FILE_NAME);
+    let content = fs::read_to_string(&entry_path).map_err(|e| AccError::IoError {
+        message: format!("Failed to read trash entry: {}", e),
+    })?;
+    serde_json::from_str(&content).map_err(|e| AccError::InvalidSetupJson {
+        file_path: entry_path.to_string_lossy().to_string(),
+        error: e.to_string(),
+    })
+}
+
+fn remove_item(setups_path: &Path, id: &str) -> AccResult<()> {
+    let item_dir = trash_dir(setups_path).join(id);
+    fs::remove_dir_all(&item_dir).map_err(|e| AccError::IoError {
+        message: format!("Failed to remove trash item: {}", e),
+    })
+}
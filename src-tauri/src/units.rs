@@ -0,0 +1,240 @@
+use crate::data;
+use crate::errors::{AccError, AccResult};
+use crate::models::{
+    ConversionScale, ParameterConversion, ParameterLimit, PhysicalValue, SetupViolation,
+};
+use crate::search::collect_parameters;
+use serde_json::Value;
+
+/// Number of decimals kept in converted values, hiding float noise such as
+/// `20.300000000000001`
+const VALUE_PRECISION: f64 = 10_000.0;
+
+/// Convert every numeric parameter of a setup from clicks to physical units.
+/// Parameters without conversion data are returned with their clicks only.
+pub fn physical_values(car_id: &str, content: &Value) -> AccResult<Vec<PhysicalValue>> {
+    let conversions = car_conversions(car_id)?;
+
+    let mut parameters = Vec::new();
+    collect_parameters("", content, &mut parameters);
+
+    Ok(parameters
+        .into_iter()
+        .map(|(path, clicks)| {
+            let converted = find_conversion(&conversions, &path).and_then(|conversion| {
+                to_value(&conversion.scale, clicks).map(|value| (conversion, value))
+            });
+            match converted {
+                Some((conversion, value)) => PhysicalValue {
+                    value: Some(round_value(value)),
+                    unit: Some(conversion.unit.clone()),
+                    step: step_at(&conversion.scale, clicks).map(round_value),
+                    clicks,
+                    path,
+                },
+                None => PhysicalValue {
+                    path,
+                    clicks,
+                    value: None,
+                    unit: None,
+                    step: None,
+                },
+            }
+        })
+        .collect())
+}
+
+/// Convert a physical value back to the nearest click of a parameter. Fails
+/// for parameters without conversion data and for clicks outside the car's
+/// setup limits.
+pub fn to_clicks(car_id: &str, path: &str, value: f64) -> AccResult<i64> {
+    if !value.is_finite() {
+        return Err(invalid_value(path, "Value must be a finite number", None));
+    }
+
+    let conversions = car_conversions(car_id)?;
+    let no_data = || AccError::NoConversionData {
+        path: path.to_string(),
+    };
+    let conversion = find_conversion(&conversions, path).ok_or_else(no_data)?;
+
+    let clicks = match &conversion.scale {
+        ConversionScale::Linear { base, step } => ((value - base) / step).round(),
+        ConversionScale::Table { values } => values
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (*a - value).abs().total_cmp(&(*b - value).abs()))
+            .map(|(click, _)| click as f64)
+            .ok_or_else(no_data)?,
+    };
+    if !clicks.is_finite() {
+        return Err(no_data());
+    }
+
+    let limits = data::get_setup_limits(car_id).unwrap_or_default();
+    if let Some(limit) = find_limit(&limits, path) {
+        if clicks < limit.min || clicks > limit.max {
+            return Err(invalid_value(
+                path,
+                &format!(
+                    "{} {} is click {}, outside the range of {} to {}",
+                    value, conversion.unit, clicks, limit.min, limit.max
+                ),
+                Some(value),
+            ));
+        }
+    }
+
+    Ok(clicks as i64)
+}
+
+/// Physical value of a click, `None` if it is not covered by a table
+fn to_value(scale: &ConversionScale, clicks: f64) -> Option<f64> {
+    match scale {
+        ConversionScale::Linear { base, step } => Some(base + clicks * step),
+        ConversionScale::Table { values } => table_click(values, clicks).map(|click| values[click]),
+    }
+}
+
+/// Physical change from a click to the next one
+fn step_at(scale: &ConversionScale, clicks: f64) -> Option<f64> {
+    match scale {
+        ConversionScale::Linear { step, .. } => Some(*step),
+        ConversionScale::Table { values } => {
+            let click = table_click(values, clicks)?;
+            values.get(click + 1).map(|next| next - values[click])
+        }
+    }
+}
+
+/// Position of a click in a table, if it is a whole click within the table
+fn table_click(values: &[f64], clicks: f64) -> Option<usize> {
+    (clicks.fract() == 0.0 && clicks >= 0.0 && clicks < values.len() as f64)
+        .then_some(clicks as usize)
+}
+
+fn invalid_value(path: &str, message: &str, value: Option<f64>) -> AccError {
+    AccError::InvalidSetupParameters {
+        violations: vec![SetupViolation {
+            path: path.to_string(),
+            message: message.to_string(),
+            value: value.map(Value::from),
+        }],
+    }
+}
+
+fn car_conversions(car_id: &str) -> AccResult<Vec<ParameterConversion>> {
+    data::get_parameter_conversions(car_id).ok_or_else(|| AccError::InvalidCarId {
+        car_id: car_id.to_string(),
+    })
+}
+
+/// Find the conversion of a concrete parameter path. Conversions for a
+/// specific array element win over ones for the whole array.
+fn find_conversion<'a>(
+    conversions: &'a [ParameterConversion],
+    path: &str,
+) -> Option<&'a ParameterConversion> {
+    let (pattern, index) = split_path(path);
+    let mut candidates = conversions
+        .iter()
+        .filter(|conversion| conversion.path == pattern);
+
+    candidates
+        .clone()
+        .find(|conversion| index.is_some() && conversion.index == index)
+        .or_else(|| candidates.find(|conversion| conversion.index.is_none()))
+}
+
+/// Find the limit of a concrete parameter path. Array limits cover every
+/// element of the array.
+fn find_limit<'a>(limits: &'a [ParameterLimit], path: &str) -> Option<&'a ParameterLimit> {
+    let (pattern, index) = split_path(path);
+    limits
+        .iter()
+        .find(|limit| limit.path == pattern && (index.is_none() || limit.length.is_some()))
+}
+
+/// Split a concrete path such as `a.b[1].c[2]` into its pattern `a.b.*.c`
+/// and the index of the trailing array element, `2`
+fn split_path(path: &str) -> (String, Option<usize>) {
+    let (base, index) = match path
+        .strip_suffix(']')
+        .and_then(|rest| rest.rsplit_once('['))
+        .and_then(|(base, index)| Some((base, index.parse::<usize>().ok()?)))
+    {
+        Some((base, index)) => (base, Some(index)),
+        None => (path, None),
+    };
+
+    let mut pattern = String::with_capacity(base.len());
+    let mut in_index = false;
+    for c in base.chars() {
+        match c {
+            '[' => {
+                pattern.push_str(".*");
+                in_index = true;
+            }
+            ']' => in_index = false,
+            _ if in_index => {}
+            _ => pattern.push(c),
+        }
+    }
+
+    (pattern, index)
+}
+
+fn round_value(value: f64) -> f64 {
+    (value * VALUE_PRECISION).round() / VALUE_PRECISION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn value_of<'a>(values: &'a [PhysicalValue], path: &str) -> &'a PhysicalValue {
+        values.iter().find(|value| value.path == path).unwrap()
+    }
+
+    #[test]
+    fn converts_clicks_to_physical_values() {
+        let content = json!({
+            "advancedSetup": {
+                "mechanicalBalance": { "wheelRate": [0, 0, 3, 3] },
+                "aeroBalance": { "rideHeight": [10, 12] },
+            },
+        });
+        let values = physical_values("bmw_m4_gt3", &content).unwrap();
+
+        let ride_height = value_of(&values, "advancedSetup.aeroBalance.rideHeight[0]");
+        assert_eq!(ride_height.value, Some(60.0));
+        assert_eq!(ride_height.unit.as_deref(), Some("mm"));
+        let ride_height = value_of(&values, "advancedSetup.aeroBalance.rideHeight[1]");
+        assert_eq!(ride_height.value, Some(67.0));
+
+        let wheel_rate = value_of(&values, "advancedSetup.mechanicalBalance.wheelRate[2]");
+        assert_eq!(wheel_rate.value, Some(140000.0));
+        assert_eq!(wheel_rate.unit.as_deref(), Some("N/m"));
+        assert_eq!(wheel_rate.step, Some(10000.0));
+    }
+
+    #[test]
+    fn converts_physical_values_back_to_clicks() {
+        let ride_height = "advancedSetup.aeroBalance.rideHeight[1]";
+        assert_eq!(to_clicks("bmw_m4_gt3", ride_height, 67.0).unwrap(), 12);
+
+        // Table conversions snap to the nearest click
+        let wheel_rate = "advancedSetup.mechanicalBalance.wheelRate[0]";
+        assert_eq!(to_clicks("bmw_m4_gt3", wheel_rate, 146000.0).unwrap(), 2);
+
+        let camber = "basicSetup.alignment.camber[0]";
+        assert_eq!(to_clicks("mercedes_amg_gt4", camber, -2.0).unwrap(), 20);
+    }
+
+    #[test]
+    fn rejects_values_outside_the_car_limits() {
+        let ride_height = "advancedSetup.aeroBalance.rideHeight[0]";
+        assert!(to_clicks("bmw_m4_gt3", ride_height, 120.0).is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::data;
+use crate::models::{AliasMatch, ParameterLimit, SetupValidation, SetupViolation};
+use log::info;
+use serde_json::Value;
+
+/// Top-level keys every setup file must contain
+const REQUIRED_FIELDS: &[&str] = &["carName", "basicSetup", "advancedSetup"];
+
+/// Element counts of array parameters, the same for every car. Corner arrays
+/// hold front left, front right, rear left, rear right; axle arrays hold
+/// front, rear.
+const ARRAY_LENGTHS: &[(&str, usize)] = &[
+    ("basicSetup.tyres.tyrePressure", 4),
+    ("basicSetup.strategy.pitStrategy.*.tyres.tyrePressure", 4),
+    ("basicSetup.alignment.camber", 4),
+    ("basicSetup.alignment.toe", 4),
+    ("advancedSetup.mechanicalBalance.wheelRate", 4),
+    ("advancedSetup.mechanicalBalance.bumpStopRateUp", 4),
+    ("advancedSetup.mechanicalBalance.bumpStopRateDown", 4),
+    ("advancedSetup.mechanicalBalance.bumpStopWindow", 4),
+    ("advancedSetup.dampers.bumpSlow", 4),
+    ("advancedSetup.dampers.bumpFast", 4),
+    ("advancedSetup.dampers.reboundSlow", 4),
+    ("advancedSetup.dampers.reboundFast", 4),
+    ("advancedSetup.aeroBalance.rodLength", 4),
+    ("advancedSetup.aeroBalance.rideHeight", 2),
+    ("advancedSetup.aeroBalance.brakeDuct", 2),
+];
+
+/// Element count of an array parameter, `None` for scalars
+pub fn array_length(path: &str) -> Option<usize> {
+    ARRAY_LENGTHS
+        .iter()
+        .find(|(array, _)| *array == path)
+        .map(|(_, length)| *length)
+}
+
+/// Validate setup content against the structure and the legal parameter
+/// ranges of `car_id`, which may also be an alias of the car. Returns every
+/// violation found, not just the first, along with the aliases `car_id` and
+/// `carName` were resolved through.
+pub fn validate_setup(car_id: &str, content: &Value) -> SetupValidation {
+    let mut violations = Vec::new();
+    let mut aliases = Vec::new();
+
+    let Some(obj) = content.as_object() else {
+        violations.push(violation("", "Setup content must be a JSON object", None));
+        return SetupValidation {
+            violations,
+            aliases,
+        };
+    };
+
+    for field in REQUIRED_FIELDS {
+        if !obj.contains_key(*field) {
+            violations.push(violation(field, "Missing required field", None));
+        }
+    }
+
+    let Some((car, alias)) = data::resolve_car(car_id) else {
+        // Unknown cars can only be checked structurally
+        check_array_lengths(content, &[], &mut violations);
+        return SetupValidation {
+            violations,
+            aliases,
+        };
+    };
+    if let Some(alias) = alias {
+        info!("Validating against car {} for '{}'", alias.id, alias.name);
+        aliases.push(alias);
+    }
+    let limits = data::get_setup_limits(&car.id).unwrap_or_default();
+
+    if let Some(car_name) = obj.get("carName") {
+        let json_car = car_name.as_str().and_then(data::resolve_car);
+        match json_car {
+            Some((json_car, alias)) if json_car.id == car.id => {
+                if let Some(alias) = alias {
+                    info!("Setup carName '{}' resolved to {}", alias.name, alias.id);
+                    push_alias(&mut aliases, alias);
+                }
+            }
+            _ => violations.push(violation(
+                "carName",
+                &format!("Car name does not match '{}'", car.id),
+                Some(car_name),
+            )),
+        }
+    }
+
+    check_array_lengths(content, &limits, &mut violations);
+    for limit in &limits {
+        check_limit(content, limit, &mut violations);
+    }
+
+    SetupValidation {
+        violations,
+        aliases,
+    }
+}
+
+/// Add an alias match unless the same name was already reported
+pub fn push_alias(aliases: &mut Vec<AliasMatch>, alias: AliasMatch) {
+    if !aliases.contains(&alias) {
+        aliases.push(alias);
+    }
+}
+
+/// Check the element count of array parameters that have no limit for the
+/// car; those with one are checked along with their range
+fn check_array_lengths(
+    content: &Value,
+    limits: &[ParameterLimit],
+    violations: &mut Vec<SetupViolation>,
+) {
+    for (pattern, length) in ARRAY_LENGTHS {
+        if limits.iter().any(|limit| limit.path == *pattern) {
+            continue;
+        }
+        for (path, value) in resolve(content, pattern) {
+            check_length(&path, value, *length, violations);
+        }
+    }
+}
+
+fn check_limit(content: &Value, limit: &ParameterLimit, violations: &mut Vec<SetupViolation>) {
+    for (path, value) in resolve(content, &limit.path) {
+        match limit.length {
+            Some(length) => {
+                let Some(items) = check_length(&path, value, length, violations) else {
+                    continue;
+                };
+
+                for (index, item) in items.iter().enumerate() {
+                    check_range(&format!("{}[{}]", path, index), item, limit, violations);
+                }
+            }
+            None => check_range(&path, value, limit, violations),
+        }
+    }
+}
+
+/// Report an array parameter that isn't an array of `length` values.
+/// Returns its elements unless it isn't an array at all.
+fn check_length<'a>(
+    path: &str,
+    value: &'a Value,
+    length: usize,
+    violations: &mut Vec<SetupViolation>,
+) -> Option<&'a Vec<Value>> {
+    let Some(items) = value.as_array() else {
+        violations.push(violation(
+            path,
+            &format!("Expected an array of {} values", length),
+            Some(value),
+        ));
+        return None;
+    };
+
+    if items.len() != length {
+        violations.push(violation(
+            path,
+            &format!("Expected {} values, found {}", length, items.len()),
+            Some(value),
+        ));
+    }
+    Some(items)
+}
+
+fn check_range(
+    path: &str,
+    value: &Value,
+    limit: &ParameterLimit,
+    violations: &mut Vec<SetupViolation>,
+) {
+    match value.as_f64() {
+        Some(number) if number < limit.min || number > limit.max => {
+            violations.push(violation(
+                path,
+                &format!("Value must be between {} and {}", limit.min, limit.max),
+                Some(value),
+            ));
+        }
+        Some(_) => {}
+        None => violations.push(violation(path, "Expected a number", Some(value))),
+    }
+}
+
+fn resolve<'a>(content: &'a Value, pattern: &str) -> Vec<(String, &'a Value)> {
+    let segments: Vec<&str> = pattern.split('.').collect();
+    let mut matches = Vec::new();
+    resolve_path(content, &segments, String::new(), &mut matches);
+    matches
+}
+
+/// Collect every value matching a dot-separated path. A `*` segment matches
+/// every element of an array. Missing parameters are not reported here, so
+/// optional keys only get range-checked when present.
+fn resolve_path<'a>(
+    value: &'a Value,
+    segments: &[&str],
+    path: String,
+    matches: &mut Vec<(String, &'a Value)>,
+) {
+    let Some((segment, rest)) = segments.split_first() else {
+        matches.push((path, value));
+        return;
+    };
+
+    if *segment == "*" {
+        if let Some(items) = value.as_array() {
+            for (index, item) in items.iter().enumerate() {
+                resolve_path(item, rest, format!("{}[{}]", path, index), matches);
+            }
+        }
+        return;
+    }
+
+    if let Some(child) = value.get(*segment) {
+        let child_path = if path.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}.{}", path, segment)
+        };
+        resolve_path(child, rest, child_path, matches);
+    }
+}
+
+fn violation(path: &str, message: &str, value: Option<&Value>) -> SetupViolation {
+    SetupViolation {
+        path: path.to_string(),
+        message: message.to_string(),
+        value: value.cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn setup(advanced: Value, electronics: Value) -> Value {
+        json!({
+            "carName": "bmw_m4_gt3",
+            "basicSetup": { "electronics": electronics },
+            "advancedSetup": advanced,
+        })
+    }
+
+    fn paths(validation: &SetupValidation) -> Vec<&str> {
+        validation
+            .violations
+            .iter()
+            .map(|violation| violation.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn rejects_a_ride_height_with_three_values() {
+        let content = setup(
+            json!({ "aeroBalance": { "rideHeight": [10, 12, 14] } }),
+            json!({ "tC1": 3 }),
+        );
+
+        let validation = validate_setup("bmw_m4_gt3", &content);
+        assert_eq!(paths(&validation), ["advancedSetup.aeroBalance.rideHeight"]);
+        assert_eq!(
+            validation.violations[0].message,
+            "Expected 2 values, found 3"
+        );
+
+        // The length doesn't depend on the car
+        let validation = validate_setup("unknown_car", &content);
+        assert!(paths(&validation).contains(&"advancedSetup.aeroBalance.rideHeight"));
+    }
+
+    #[test]
+    fn rejects_a_traction_control_map_out_of_range() {
+        let content = setup(
+            json!({ "aeroBalance": { "rideHeight": [10, 14] } }),
+            json!({ "tC1": 12 }),
+        );
+
+        let validation = validate_setup("bmw_m4_gt3", &content);
+        assert_eq!(paths(&validation), ["basicSetup.electronics.tC1"]);
+    }
+
+    #[test]
+    fn accepts_values_inside_the_car_limits() {
+        let content = setup(
+            json!({ "aeroBalance": { "rideHeight": [10, 14] } }),
+            json!({ "tC1": 11, "tC2": 0, "eCUMap": 8 }),
+        );
+
+        assert!(validate_setup("bmw_m4_gt3", &content).violations.is_empty());
+    }
+}
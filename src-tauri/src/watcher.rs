@@ -1,29 +1,128 @@
 use crate::errors::{AccError, AccResult};
-use crate::state::AppStateManager;
+use crate::models::{WatcherState, WatcherStatus};
+use crate::state::{CacheUpdate, Library};
 use log::{error, info, warn};
+use notify::event::ModifyKind;
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{AppHandle, Emitter};
 use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Upper bound on how long a continuous burst is held back, in debounce windows
+const MAX_BURST_FACTOR: u32 = 10;
+
+/// How often the watcher checks that the setups folder is still there
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// File watchers keyed by library id, shared through Tauri managed state so
+/// they can be re-targeted, started and stopped
+pub type SharedWatcher = Arc<tokio::sync::Mutex<HashMap<String, FileWatcher>>>;
+
+/// Identity of a folder on disk, which changes when it is deleted and recreated
+#[cfg(unix)]
+type FolderId = (u64, u64, Option<std::time::SystemTime>);
+#[cfg(not(unix))]
+type FolderId = std::time::SystemTime;
+
+/// The notify watcher plus the path it should be and is watching
+struct WatchState {
+    library_id: String,
+    watcher: RecommendedWatcher,
+    target: PathBuf,
+    /// Watched path along with the identity of the folder it was when the
+    /// watch started
+    watching: Option<(PathBuf, FolderId)>,
+    status: Option<WatcherState>,
+}
+
+impl WatchState {
+    /// Bring the notify registration in line with the target path. Returns
+    /// true if watching (re)started, meaning the cache may be stale.
+    fn sync(&mut self, app_handle: &AppHandle) -> bool {
+        // A folder that was deleted and recreated keeps its path, but the
+        // watch on the old folder is gone
+        let target_id = folder_id(&self.target);
+        let up_to_date = match (&self.watching, target_id) {
+            (Some((path, id)), Some(target_id)) => *path == self.target && *id == target_id,
+            _ => false,
+        };
+        if up_to_date {
+            return false;
+        }
+
+        if let Some((old_path, _)) = self.watching.take() {
+            // The folder may already be gone, so failures here are expected
+            let _ = self.watcher.unwatch(&old_path);
+        }
+
+        let Some(target_id) = target_id else {
+            warn!("Setups directory does not exist: {:?}", self.target);
+            self.set_status(
+                app_handle,
+                WatcherState::Unavailable,
+                Some("Setups folder does not exist".to_string()),
+            );
+            return false;
+        };
+
+        match self.watcher.watch(&self.target, RecursiveMode::Recursive) {
+            Ok(()) => {
+                info!("Started watching setups directory: {:?}", self.target);
+                self.watching = Some((self.target.clone(), target_id));
+                self.set_status(app_handle, WatcherState::Watching, None);
+                true
+            }
+            Err(e) => {
+                error!("Failed to start watching directory: {}", e);
+                self.set_status(app_handle, WatcherState::Error, Some(e.to_string()));
+                false
+            }
+        }
+    }
+
+    /// Emit a `watcher-status` event when the state changes
+    fn set_status(&mut self, app_handle: &AppHandle, state: WatcherState, message: Option<String>) {
+        if self.status == Some(state) {
+            return;
+        }
+        self.status = Some(state);
+
+        let status = WatcherStatus {
+            library_id: self.library_id.clone(),
+            path: self.target.to_string_lossy().to_string(),
+            state,
+            message,
+        };
+        if let Err(e) = app_handle.emit("watcher-status", &status) {
+            error!("Failed to emit watcher-status event: {}", e);
+        }
+    }
+}
 
 /// File system watcher for monitoring setups folder changes
 pub struct FileWatcher {
-    _watcher: RecommendedWatcher,
+    inner: Arc<Mutex<WatchState>>,
+    app_handle: AppHandle,
 }
 
 impl FileWatcher {
-    /// Create and start a new file watcher
+    /// Create and start a file watcher for a library. A missing setups folder
+    /// is not an error: the watcher reports it as unavailable and starts
+    /// watching once the folder appears.
     pub fn new(
         setups_path: &Path,
-        state_manager: Arc<AppStateManager>,
+        library: Arc<Library>,
         app_handle: AppHandle,
     ) -> AccResult<Self> {
         let (tx, mut rx) = mpsc::unbounded_channel();
-        let setups_path = setups_path.to_path_buf();
 
         // Create the watcher
-        let mut watcher = RecommendedWatcher::new(
+        let watcher = RecommendedWatcher::new(
             move |res: Result<Event, notify::Error>| {
                 if let Err(e) = tx.send(res) {
                     error!("Failed to send file watcher event: {}", e);
@@ -36,106 +135,260 @@ impl FileWatcher {
         })?;
 
         // Start watching the setups directory
-        if setups_path.exists() {
-            watcher
-                .watch(&setups_path, RecursiveMode::Recursive)
-                .map_err(|e| AccError::IoError {
-                    message: format!("Failed to start watching directory: {}", e),
-                })?;
-            info!("Started watching setups directory: {:?}", setups_path);
-        } else {
-            warn!("Setups directory does not exist: {:?}", setups_path);
-        }
+        let mut watch_state = WatchState {
+            library_id: library.id().to_string(),
+            watcher,
+            target: setups_path.to_path_buf(),
+            watching: None,
+            status: None,
+        };
+        watch_state.sync(&app_handle);
+        let inner = Arc::new(Mutex::new(watch_state));
+
+        // Spawn the health check task; it stops once the watcher is dropped
+        let weak_inner = Arc::downgrade(&inner);
+        let health_library = Arc::clone(&library);
+        let health_handle = app_handle.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(inner) = weak_inner.upgrade() else {
+                    break;
+                };
+                let recovered = match inner.lock() {
+                    Ok(mut watch_state) => watch_state.sync(&health_handle),
+                    Err(_) => break,
+                };
+                drop(inner);
+
+                if recovered {
+                    info!("Setups directory is available again, rescanning");
+                    Self::emit_full_refresh(&health_library, &health_handle).await;
+                }
+            }
+        });
 
         // Spawn the event handler task
-        let state_manager_clone = Arc::clone(&state_manager);
+        let event_library = Arc::clone(&library);
+        let event_handle = app_handle.clone();
         tokio::spawn(async move {
-            while let Some(result) = rx.recv().await {
-                match result {
-                    Ok(event) => {
-                        if let Err(e) = Self::handle_file_event(
-                            event,
-                            &state_manager_clone,
-                            &app_handle,
-                            &setups_path,
-                        )
-                        .await
-                        {
-                            error!("Error handling file event: {}", e);
+            while let Some(first) = rx.recv().await {
+                // Coalesce bursts of events into a single batch
+                let debounce = event_library.get_watcher_debounce().await;
+                let mut batch = vec![first];
+                Self::collect_burst(&mut rx, debounce, &mut batch).await;
+
+                let events: Vec<Event> = batch
+                    .into_iter()
+                    .filter_map(|result| match result {
+                        Ok(event) => Some(event),
+                        Err(e) => {
+                            error!("File watcher error: {}", e);
+                            None
                         }
-                    }
-                    Err(e) => {
-                        error!("File watcher error: {}", e);
-                    }
+                    })
+                    .collect();
+
+                // Read the path per batch so events follow set_setups_path
+                let setups_path = event_library.get_setups_path().await;
+                if let Err(e) =
+                    Self::handle_file_events(events, &event_library, &event_handle, &setups_path)
+                        .await
+                {
+                    error!("Error handling file events: {}", e);
                 }
             }
         });
 
-        Ok(Self { _watcher: watcher })
+        Ok(Self { inner, app_handle })
     }
 
-    /// Handle individual file system events
-    async fn handle_file_event(
-        event: Event,
-        state_manager: &AppStateManager,
+    /// Keep receiving events until the channel has been quiet for `debounce`,
+    /// or until the burst has lasted `MAX_BURST_FACTOR` debounce windows
+    async fn collect_burst(
+        rx: &mut mpsc::UnboundedReceiver<Result<Event, notify::Error>>,
+        debounce: Duration,
+        batch: &mut Vec<Result<Event, notify::Error>>,
+    ) {
+        let deadline = Instant::now() + debounce * MAX_BURST_FACTOR;
+        loop {
+            let wait = debounce.min(deadline.saturating_duration_since(Instant::now()));
+            match tokio::time::timeout(wait, rx.recv()).await {
+                Ok(Some(result)) => batch.push(result),
+                // Channel closed or quiet period elapsed
+                Ok(None) | Err(_) => break,
+            }
+        }
+    }
+
+    /// Handle a coalesced batch of file system events
+    async fn handle_file_events(
+        events: Vec<Event>,
+        library: &Library,
         app_handle: &AppHandle,
         setups_path: &Path,
     ) -> AccResult<()> {
+        let mut changed_files = HashSet::new();
+        let mut needs_rescan = false;
+
         // Only handle events that affect the folder structure
-        match &event.kind {
-            EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_) => {
-                // Check if the event affects .json files or directories
-                let should_update = event.paths.iter().any(|path| {
-                    // Check if it's a .json file or a directory change within setups_path
-                    if let Ok(relative_path) = path.strip_prefix(setups_path) {
-                        // Update if it's a JSON file, or if it's a directory change
-                        path.extension() == Some("json".as_ref())
-                            || path.is_dir()
-                            || relative_path.components().count() <= 3 // Only car/track/setup levels
-                    } else {
-                        false
-                    }
-                });
+        for event in events.iter().filter(|event| {
+            matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_)
+            )
+        }) {
+            for path in &event.paths {
+                let Ok(relative_path) = path.strip_prefix(setups_path) else {
+                    continue;
+                };
 
-                if should_update {
-                    info!("File system change detected, updating structure");
+                // Ignore hidden folders such as the app's own history store
+                if is_hidden(relative_path) {
+                    continue;
+                }
 
-                    // Update the cached folder structure
-                    if let Err(e) = state_manager.refresh_folder_structure().await {
-                        error!("Failed to refresh folder structure: {}", e);
-                        return Ok(());
+                let depth = relative_path.components().count();
+                if depth == 3 && path.extension() == Some("json".as_ref()) {
+                    // Writes made by save/delete are already in the cache
+                    if !library.is_own_write(path) {
+                        changed_files.insert(path.clone());
                     }
-
-                    // Get the updated structure and emit event
-                    match state_manager.get_folder_structure().await {
-                        Ok(structure) => {
-                            if let Err(e) = app_handle.emit("setups-changed", &structure) {
-                                error!("Failed to emit setups-changed event: {}", e);
-                            } else {
-                                info!(
-                                    "Emitted setups-changed event with {} cars",
-                                    structure.cars.len()
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to get folder structure for event emission: {}", e);
-                        }
+                } else if depth < 3 {
+                    // Content/metadata updates of car or track folders are caused
+                    // by changes to files inside them, which arrive separately
+                    let folder_touched = matches!(
+                        event.kind,
+                        EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Metadata(_))
+                    );
+                    if !folder_touched {
+                        needs_rescan = true;
                     }
                 }
             }
-            _ => {
-                // Ignore other event types (access, metadata changes, etc.)
+        }
+
+        let mut deltas = Vec::new();
+        if !needs_rescan {
+            // Only individual setup files changed: patch the cache in place
+            for path in &changed_files {
+                match library.apply_setup_change(path).await? {
+                    CacheUpdate::Applied(delta) => deltas.push(*delta),
+                    CacheUpdate::Ignored => {}
+                    CacheUpdate::NeedsRescan => {
+                        needs_rescan = true;
+                        break;
+                    }
+                }
             }
         }
 
+        if needs_rescan {
+            info!("Folder change detected, rescanning setups folder");
+            Self::emit_full_refresh(library, app_handle).await;
+            return Ok(());
+        }
+
+        if deltas.is_empty() {
+            return Ok(());
+        }
+
+        for delta in &deltas {
+            if let Err(e) = app_handle.emit("setups-delta", delta) {
+                error!("Failed to emit setups-delta event: {}", e);
+            }
+        }
+        info!("Applied {} incremental setup changes", deltas.len());
         Ok(())
     }
 
-    /// Update the watched directory (call this when setups path changes)
-    pub fn update_watch_path(&mut self, _new_path: &Path) -> AccResult<()> {
-        // The watcher is recreated when the path changes in the state manager
-        // This method is kept for future extensibility
+    /// Rescan the whole setups folder and emit the new structure
+    async fn emit_full_refresh(library: &Library, app_handle: &AppHandle) {
+        if let Err(e) = library.refresh_folder_structure().await {
+            error!("Failed to refresh folder structure: {}", e);
+            return;
+        }
+        Self::emit_structure(library, app_handle).await;
+    }
+
+    /// Emit the cached folder structure on the `setups-changed` event
+    async fn emit_structure(library: &Library, app_handle: &AppHandle) {
+        match library.get_folder_structure().await {
+            Ok(structure) => {
+                if let Err(e) = app_handle.emit("setups-changed", &structure) {
+                    error!("Failed to emit setups-changed event: {}", e);
+                } else {
+                    info!(
+                        "Emitted setups-changed event with {} cars",
+                        structure.cars.len()
+                    );
+                }
+            }
+            Err(e) => {
+                error!("Failed to get folder structure for event emission: {}", e);
+            }
+        }
+    }
+
+    /// Point the watcher at a new setups directory (call this when the
+    /// setups path changes)
+    pub fn update_watch_path(&mut self, new_path: &Path) -> AccResult<()> {
+        let mut watch_state = self.inner.lock().map_err(|e| AccError::IoError {
+            message: format!("File watcher state is poisoned: {}", e),
+        })?;
+
+        if watch_state.target != new_path {
+            watch_state.target = new_path.to_path_buf();
+            watch_state.status = None;
+        }
+        watch_state.sync(&self.app_handle);
         Ok(())
     }
 }
+
+/// Identity of a directory, `None` if the path is not one
+fn folder_id(path: &Path) -> Option<FolderId> {
+    let metadata = fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_dir())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // Inode numbers can be reused right away, creation times can't
+        Some((metadata.dev(), metadata.ino(), metadata.created().ok()))
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.created().ok()
+    }
+}
+
+/// Check whether any component of a relative path is hidden (starts with a dot)
+fn is_hidden(relative_path: &Path) -> bool {
+    relative_path
+        .components()
+        .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_recreated_folder_has_a_new_identity() {
+        let root = std::env::temp_dir().join(format!("accsm_watch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let original = folder_id(&root);
+        assert!(original.is_some());
+        assert_eq!(folder_id(&root), original);
+
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(folder_id(&root), None);
+        fs::create_dir_all(&root).unwrap();
+        assert_ne!(folder_id(&root), original);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
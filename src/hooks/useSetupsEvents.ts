@@ -3,29 +3,110 @@ import { useEffect } from "react";
 import { toast } from "sonner";
 import { queryKeys } from "@/hooks/useBackend";
 import { TauriAPI } from "@/services/api";
-import type { FolderStructure } from "@/types/backend";
+import type { FolderStructure, SetupsDelta } from "@/types/backend";
+
+// Apply a single-setup change to the cached folder structure. Returns
+// undefined if the change lands in a car or track folder the cache doesn't
+// have yet, as the delta doesn't carry the folder names.
+function applyDelta(
+    structure: FolderStructure,
+    delta: SetupsDelta,
+): FolderStructure | undefined {
+    const car = structure.cars.find((c) => c.car_id === delta.car_id);
+    const track = car?.tracks.find((t) => t.track_id === delta.track_id);
+
+    if (delta.kind === "upserted" && (!car || !track || !delta.setup)) {
+        return undefined;
+    }
+    if (!car || !track) {
+        return { ...structure, total_setups: delta.total_setups };
+    }
+
+    let setups = track.setups.filter((s) => s.filename !== delta.filename);
+    if (delta.kind === "upserted" && delta.setup) {
+        setups = [...setups, delta.setup].sort((a, b) =>
+            a.display_name.localeCompare(b.display_name),
+        );
+    }
+
+    const tracks = car.tracks
+        .map((t) => (t === track ? { ...t, setups } : t))
+        .filter((t) => t.setups.length > 0);
+
+    return {
+        ...structure,
+        cars: structure.cars.map((c) => (c === car ? { ...c, tracks } : c)),
+        total_setups: delta.total_setups,
+    };
+}
 
 export function useSetupsEvents() {
     const queryClient = useQueryClient();
 
     useEffect(() => {
-        let unlisten: (() => void) | undefined;
+        const unlisteners: (() => void)[] = [];
 
         const setupListener = async () => {
             try {
-                unlisten = await TauriAPI.onSetupsChanged(
-                    (structure: FolderStructure) => {
-                        // Update the cached folder structure
-                        queryClient.setQueryData(
-                            queryKeys.folderStructure,
-                            structure,
-                        );
-
-                        // Show a toast notification
-                        toast.info("Setups folder updated", {
-                            description: `Found ${structure.total_setups} setups across ${structure.cars.length} cars`,
-                        });
-                    },
+                // Full rescans send the whole tree
+                unlisteners.push(
+                    await TauriAPI.onSetupsChanged(
+                        (structure: FolderStructure) => {
+                            // Every library emits its own tree; only the one
+                            // the UI shows is cached
+                            const current =
+                                queryClient.getQueryData<FolderStructure>(
+                                    queryKeys.folderStructure,
+                                );
+                            if (!current) {
+                                queryClient.invalidateQueries({
+                                    queryKey: queryKeys.folderStructure,
+                                });
+                                return;
+                            }
+                            if (current.library_id !== structure.library_id) {
+                                return;
+                            }
+
+                            queryClient.setQueryData(
+                                queryKeys.folderStructure,
+                                structure,
+                            );
+
+                            toast.info("Setups folder updated", {
+                                description: `Found ${structure.total_setups} setups across ${structure.cars.length} cars`,
+                            });
+                        },
+                    ),
+                );
+
+                // Single file changes only send the changed setup
+                unlisteners.push(
+                    await TauriAPI.onSetupsDelta((delta: SetupsDelta) => {
+                        const current =
+                            queryClient.getQueryData<FolderStructure>(
+                                queryKeys.folderStructure,
+                            );
+                        if (
+                            !current ||
+                            current.library_id !== delta.library_id
+                        ) {
+                            return;
+                        }
+                        const updated = applyDelta(current, delta);
+
+                        if (updated) {
+                            queryClient.setQueryData(
+                                queryKeys.folderStructure,
+                                updated,
+                            );
+                        } else {
+                            // Fetch the backend's cached tree instead
+                            queryClient.invalidateQueries({
+                                queryKey: queryKeys.folderStructure,
+                            });
+                        }
+                    }),
                 );
             } catch (error) {
                 console.error("Failed to setup event listener:", error);
@@ -35,7 +116,7 @@ export function useSetupsEvents() {
         setupListener();
 
         return () => {
-            if (unlisten) {
+            for (const unlisten of unlisteners) {
                 unlisten();
             }
         };
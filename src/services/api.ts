@@ -2,15 +2,32 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import type {
+    AliasMatch,
+    AppConfig,
     Car,
     DeleteSetupParams,
     FolderStructure,
+    FuelStrategy,
+    FuelStrategyRequest,
     GetSetupParams,
+    LibraryConfig,
+    ParameterConversion,
+    PhysicalSetup,
     SaveSetupParams,
+    ScanDiagnostic,
+    SearchResult,
     SetupFile,
+    SetupInfo,
+    SetupMetadata,
+    SetupQuery,
+    SetupRef,
     SetupsChangedEvent,
+    SetupsDelta,
+    TagUpdateReport,
+    TagUsage,
     Track,
     ValidateSetupParams,
+    WatcherStatus,
 } from "@/types/backend";
 
 export class TauriAPI {
@@ -24,6 +41,10 @@ export class TauriAPI {
     }
 
     // Setup operations
+    static async getScanDiagnostics(): Promise<ScanDiagnostic[]> {
+        return invoke<ScanDiagnostic[]>("get_scan_diagnostics");
+    }
+
     static async getSetup(params: GetSetupParams): Promise<SetupFile> {
         return invoke<SetupFile>("get_setup", params);
     }
@@ -44,8 +65,46 @@ export class TauriAPI {
         return invoke<boolean>("setup_exists", params);
     }
 
-    static async validateSetup(params: ValidateSetupParams): Promise<boolean> {
-        return invoke<boolean>("validate_setup", params);
+    static async validateSetup(
+        params: ValidateSetupParams,
+    ): Promise<AliasMatch[]> {
+        return invoke<AliasMatch[]>("validate_setup", params);
+    }
+
+    static async getSetupPhysicalValues(
+        params: GetSetupParams,
+    ): Promise<PhysicalSetup> {
+        return invoke<PhysicalSetup>("get_setup_physical_values", params);
+    }
+
+    static async calculateFuelStrategy(
+        params: GetSetupParams,
+        request: FuelStrategyRequest,
+        apply?: boolean,
+    ): Promise<FuelStrategy> {
+        return invoke<FuelStrategy>("calculate_fuel_strategy", {
+            ...params,
+            request,
+            apply,
+        });
+    }
+
+    static async updateSetupMetadata(
+        car: string,
+        track: string,
+        filename: string,
+        metadata: SetupMetadata,
+    ): Promise<SetupInfo> {
+        return invoke<SetupInfo>("update_setup_metadata", {
+            car,
+            track,
+            filename,
+            metadata,
+        });
+    }
+
+    static async findSetupById(id: string): Promise<SetupRef | null> {
+        return invoke<SetupRef | null>("find_setup_by_id", { id });
     }
 
     // Path operations
@@ -57,6 +116,61 @@ export class TauriAPI {
         return invoke<void>("set_setups_path", { path });
     }
 
+    // Search operations
+    static async searchSetups(query: SetupQuery): Promise<SearchResult[]> {
+        return invoke<SearchResult[]>("search_setups", { query });
+    }
+
+    // Tag operations
+    static async listTags(): Promise<TagUsage[]> {
+        return invoke<TagUsage[]>("list_tags");
+    }
+
+    static async renameTag(from: string, to: string): Promise<TagUpdateReport> {
+        return invoke<TagUpdateReport>("rename_tag", { from, to });
+    }
+
+    static async mergeTags(
+        sources: string[],
+        target: string,
+    ): Promise<TagUpdateReport> {
+        return invoke<TagUpdateReport>("merge_tags", { sources, target });
+    }
+
+    static async deleteTag(tag: string): Promise<TagUpdateReport> {
+        return invoke<TagUpdateReport>("delete_tag", { tag });
+    }
+
+    // Config operations
+    static async getConfig(): Promise<AppConfig> {
+        return invoke<AppConfig>("get_config");
+    }
+
+    static async updateConfig(config: AppConfig): Promise<AppConfig> {
+        return invoke<AppConfig>("update_config", { config });
+    }
+
+    // Library operations
+    static async listLibraries(): Promise<LibraryConfig[]> {
+        return invoke<LibraryConfig[]>("list_libraries");
+    }
+
+    static async addLibrary(
+        name: string,
+        path: string,
+        live?: boolean,
+    ): Promise<LibraryConfig> {
+        return invoke<LibraryConfig>("add_library", { name, path, live });
+    }
+
+    static async removeLibrary(id: string): Promise<void> {
+        return invoke<void>("remove_library", { id });
+    }
+
+    static async setLiveLibrary(id: string): Promise<void> {
+        return invoke<void>("set_live_library", { id });
+    }
+
     // Data operations
     static async getCars(): Promise<Record<string, Car>> {
         return invoke<Record<string, Car>>("get_cars");
@@ -66,6 +180,22 @@ export class TauriAPI {
         return invoke<Record<string, Track>>("get_tracks");
     }
 
+    static async getParameterConversions(
+        car: string,
+    ): Promise<ParameterConversion[]> {
+        return invoke<ParameterConversion[]>("get_parameter_conversions", {
+            car,
+        });
+    }
+
+    static async physicalValueToClicks(
+        car: string,
+        path: string,
+        value: number,
+    ): Promise<number> {
+        return invoke<number>("physical_value_to_clicks", { car, path, value });
+    }
+
     // Event listeners
     static async onSetupsChanged(
         callback: (structure: FolderStructure) => void,
@@ -74,4 +204,16 @@ export class TauriAPI {
             callback(event.payload);
         });
     }
+
+    static async onWatcherStatus(callback: (status: WatcherStatus) => void) {
+        return listen<WatcherStatus>("watcher-status", (event) => {
+            callback(event.payload);
+        });
+    }
+
+    static async onSetupsDelta(callback: (delta: SetupsDelta) => void) {
+        return listen<SetupsDelta>("setups-delta", (event) => {
+            callback(event.payload);
+        });
+    }
 }
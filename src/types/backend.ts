@@ -1,19 +1,43 @@
 // Backend data structure types that match the Rust models
 
+export type CarClass = "GT3" | "GT4" | "GT2" | "GTC" | "TCX";
+
 export interface Car {
     id: string;
     pretty_name: string;
+    class: CarClass;
+    manufacturer: string;
+    year: number | null;
+    dlc: string | null;
+    fuel_tank: number | null; // liters, null if unknown
+    aliases?: string[];
 }
 
 export interface Track {
     id: string;
     pretty_name: string;
+    dlc: string | null;
+    aliases?: string[];
 }
 
 export interface AccsmData {
+    id?: string;
     lastModified: string; // ISO string from DateTime<Utc>
     tags: string[];
     setupType: string;
+    author?: string;
+    notes?: string;
+    sourceUrl?: string;
+    gameVersion?: string;
+    conditions?: TrackConditions;
+    rating?: number;
+}
+
+export interface TrackConditions {
+    airTempMin?: number;
+    airTempMax?: number;
+    trackTempMin?: number;
+    trackTempMax?: number;
 }
 
 export interface SetupFile {
@@ -25,30 +49,202 @@ export interface SetupFile {
     [key: string]: any; // For other_fields
 }
 
+export type ConversionScale =
+    | { kind: "linear"; base: number; step: number }
+    | { kind: "table"; values: number[] };
+
+export type ParameterConversion = {
+    path: string;
+    index: number | null;
+    unit: string;
+} & ConversionScale;
+
+// Parameters without conversion data only carry their clicks
+export interface PhysicalValue {
+    path: string;
+    clicks: number;
+    value: number | null;
+    unit: string | null;
+    step: number | null;
+}
+
+export interface PhysicalSetup {
+    setup: SetupFile;
+    parameters: PhysicalValue[];
+}
+
+export type RaceLength = { laps: number } | { minutes: number };
+
+export interface FuelStrategyRequest {
+    race_length: RaceLength;
+    lap_time?: number;
+    fuel_per_lap?: number;
+    safety_margin_laps?: number;
+    formation_lap?: boolean;
+    pit_stops?: number;
+}
+
+export interface FuelStint {
+    laps: number;
+    start_fuel: number;
+    fuel_used: number;
+    end_fuel: number;
+}
+
+export interface FuelStrategy {
+    total_laps: number;
+    fuel_per_lap: number;
+    fuel_tank: number;
+    total_fuel: number;
+    start_fuel: number;
+    pit_stops: number;
+    fuel_to_add: number[];
+    stints: FuelStint[];
+}
+
 export interface SetupInfo {
+    id: string | null;
     filename: string;
     display_name: string;
     last_modified: string; // ISO string
     tags: string[];
     setup_type: string;
+    author: string | null;
+    rating: number | null;
+    managed: boolean; // false for plain ACC files without ACCSMData
+}
+
+export interface AliasMatch {
+    name: string;
+    id: string;
+    alias: string | null;
 }
 
 export interface TrackFolder {
     track_id: string;
     track_name: string;
+    alias: AliasMatch | null;
     setups: SetupInfo[];
 }
 
 export interface CarFolder {
     car_id: string;
     car_name: string;
+    alias: AliasMatch | null;
     tracks: TrackFolder[];
 }
 
 export interface FolderStructure {
+    library_id: string;
     cars: CarFolder[];
     total_setups: number;
     last_scan: string; // ISO string
+    diagnostics: ScanDiagnostic[];
+}
+
+export interface AccError {
+    type: string;
+    message: Record<string, any>;
+}
+
+export interface ScanDiagnostic {
+    path: string;
+    reason: string;
+    error: AccError;
+}
+
+export type SetupChangeKind = "upserted" | "removed";
+
+export interface SetupsDelta {
+    library_id: string;
+    car_id: string;
+    track_id: string;
+    filename: string;
+    kind: SetupChangeKind;
+    setup: SetupInfo | null;
+    total_setups: number;
+}
+
+export type WatcherState = "watching" | "unavailable" | "error";
+
+export interface WatcherStatus {
+    library_id: string;
+    path: string;
+    state: WatcherState;
+    message: string | null;
+}
+
+export interface UiPreferences {
+    theme: string;
+    [key: string]: any;
+}
+
+export interface LibraryConfig {
+    id: string;
+    name: string;
+    path: string;
+    live: boolean;
+}
+
+export interface AppConfig {
+    version: number;
+    libraries: LibraryConfig[];
+    ui: UiPreferences;
+    default_tags: string[];
+    default_setup_type: string;
+    trash_retention_days: number;
+    watcher_debounce_ms: number;
+    [key: string]: any;
+}
+
+export interface SetupQuery {
+    text?: string;
+    car?: string;
+    track?: string;
+    setup_type?: string;
+    tags?: string[];
+    modified_after?: string; // ISO string
+    modified_before?: string; // ISO string
+    parameters?: string[];
+    limit?: number;
+}
+
+export interface SearchResult {
+    library_id: string;
+    car_id: string;
+    car_name: string;
+    track_id: string;
+    track_name: string;
+    setup: SetupInfo;
+    score: number;
+}
+
+export interface SetupRef {
+    car: string;
+    track: string;
+    filename: string;
+}
+
+export interface TagUsage {
+    tag: string;
+    count: number;
+}
+
+export interface TagUpdateReport {
+    changed: SetupRef[];
+    failed: SetupRef[];
+}
+
+// Omitted fields keep the stored value, null clears it
+export interface SetupMetadata {
+    tags?: string[] | null;
+    setup_type?: string | null;
+    author?: string | null;
+    notes?: string | null;
+    source_url?: string | null;
+    game_version?: string | null;
+    conditions?: TrackConditions | null;
+    rating?: number | null;
 }
 
 // Tauri command parameter types